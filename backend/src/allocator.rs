@@ -0,0 +1,889 @@
+// allocator.rs
+
+use crate::events::PresaleCode;
+use crate::seat_manager::{Distancing, Seat, SeatKey, SeatMap};
+use crate::venue::{CategoryId, SeatAttributes, Section, SectionId, Venue};
+use serde::Serialize;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+
+/// Cantidad de sugerencias que se ofrecen si el pedido no indica otra.
+pub const DEFAULT_SUGGESTIONS: usize = 3;
+
+/// Cantidad máxima de sugerencias que puede pedir un cliente.
+pub const MAX_SUGGESTIONS: usize = 10;
+
+/// Cantidad máxima de ofertas de la categoría superior que se agregan a una búsqueda.
+pub const UPGRADE_SUGGESTIONS: usize = 2;
+
+/// Peso de cada criterio en la puntuación de un bloque de asientos.
+/// Cada criterio vale entre 0 y 1, y la puntuación es la suma ponderada.
+#[derive(Debug, Clone, Copy)]
+pub struct ScoreWeights {
+    /// Visibilidad de los asientos respecto de la mejor del recinto.
+    pub visibility: f32,
+    /// Cercanía de las filas al frente de la sección.
+    pub row: f32,
+    /// Cercanía de los asientos al centro de su fila.
+    pub centrality: f32,
+    /// Cantidad de grupos en que queda dividido el bloque (uno solo es lo ideal).
+    pub contiguity: f32,
+    /// Precio respecto del asiento libre más barato de la categoría.
+    pub price: f32,
+}
+
+/// Pesos con los que se ordenan las sugerencias.
+pub const SCORE_WEIGHTS: ScoreWeights = ScoreWeights {
+    visibility: 0.30,
+    row: 0.20,
+    centrality: 0.15,
+    contiguity: 0.25,
+    price: 0.10,
+};
+
+/// Factor que se aplica a la visibilidad de los asientos con visibilidad reducida.
+pub const RESTRICTED_VIEW_FACTOR: f32 = 0.5;
+
+/// Regla para no dejar asientos libres sueltos dentro de una fila al sugerir un bloque.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrphanRule {
+    /// Se permite dejar cualquier cantidad de asientos sueltos.
+    Off,
+    /// Se evita dejar un asiento libre aislado.
+    Singles,
+    /// Se evita dejar un asiento o una pareja de asientos libres aislados.
+    Pairs,
+}
+
+impl OrphanRule {
+    /// Interpreta el nombre de la regla: `off`, `singles` o `pairs`.
+    pub fn parse(value: &str) -> Option<OrphanRule> {
+        match value {
+            "off" => Some(OrphanRule::Off),
+            "singles" => Some(OrphanRule::Singles),
+            "pairs" => Some(OrphanRule::Pairs),
+            _ => None,
+        }
+    }
+
+    /// Tramo libre más largo que la regla considera suelto.
+    fn max_gap(self) -> usize {
+        match self {
+            OrphanRule::Off => 0,
+            OrphanRule::Singles => 1,
+            OrphanRule::Pairs => 2,
+        }
+    }
+}
+
+/// Pedido de asientos de un cliente.
+#[derive(Debug, Clone)]
+pub struct SeatRequest {
+    pub category: CategoryId,
+    pub seats_amount: u32,
+    /// Solo ofrecer bloques con los asientos juntos (ver `Contiguity::is_together`).
+    pub together: bool,
+    /// Cantidad máxima de sugerencias a ofrecer.
+    pub max_suggestions: usize,
+    /// Precio total máximo de cada sugerencia.
+    pub max_total_price: Option<f32>,
+    /// Secciones cuyas sugerencias se ofrecen antes que las demás.
+    pub preferred_sections: Vec<SectionId>,
+    /// Secciones que no deben sugerirse.
+    pub excluded_sections: Vec<SectionId>,
+    /// Buscar en las categorías inferiores si la pedida no tiene lugar.
+    pub allow_downgrade: bool,
+    /// Agregar ofertas de la categoría superior a la pedida.
+    pub include_upgrades: bool,
+    /// Espacios para silla de ruedas pedidos; el resto de los asientos son sus acompañantes.
+    pub wheelchair_spaces: u32,
+    /// Código de preventa del pedido: solo se sugieren las secciones que habilita.
+    pub presale: Option<PresaleCode>,
+}
+
+impl SeatRequest {
+    /// Indica si el pedido es de asientos accesibles.
+    fn is_accessible(&self) -> bool {
+        self.wheelchair_spaces > 0
+    }
+
+    /// Indica si el pedido puede ocupar un asiento con estas características: los espacios
+    /// para silla de ruedas y los de sus acompañantes quedan reservados a los pedidos accesibles.
+    fn can_take(&self, attributes: &SeatAttributes) -> bool {
+        self.is_accessible() || !attributes.is_accessible()
+    }
+
+    /// Indica si todos los asientos están en secciones preferidas.
+    fn is_preferred(&self, seats: &[SeatKey]) -> bool {
+        !self.preferred_sections.is_empty()
+            && seats
+                .iter()
+                .all(|(section, _, _)| self.preferred_sections.contains(section))
+    }
+}
+
+/// Bloque de asientos sugerido por el buscador.
+#[derive(Debug, Clone)]
+pub struct Suggestion {
+    pub seats: Vec<SeatKey>,
+    /// Categoría de los asientos (puede ser inferior o superior a la pedida).
+    pub category: CategoryId,
+    /// Es una oferta de la categoría superior a la pedida.
+    pub upgrade: bool,
+}
+
+/// Cómo quedan repartidos los asientos de una sugerencia.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Contiguity {
+    /// Todos los asientos consecutivos en una misma fila.
+    SingleRow,
+    /// Tramos consecutivos en filas seguidas de una sección, uno detrás del otro.
+    StackedRows,
+    /// Asientos de una misma sección separados entre sí.
+    SplitRows,
+    /// Asientos de más de una sección.
+    SplitSections,
+}
+
+impl Contiguity {
+    /// Describe cómo quedan repartidos los asientos indicados.
+    pub fn of(seats: &[SeatKey]) -> Contiguity {
+        let mut seats = seats.to_vec();
+        seats.sort();
+        if seats.windows(2).any(|pair| pair[0].0 != pair[1].0) {
+            return Contiguity::SplitSections;
+        }
+
+        // Rango de números de cada fila, en orden de fila
+        let mut rows: Vec<(u32, u32, u32)> = Vec::new();
+        for (_, row, number) in &seats {
+            match rows.last_mut() {
+                Some((last_row, _, last)) if last_row == row => {
+                    if *number != *last + 1 {
+                        return Contiguity::SplitRows;
+                    }
+                    *last = *number;
+                }
+                _ => rows.push((*row, *number, *number)),
+            }
+        }
+
+        let stacked = rows.windows(2).all(|pair| {
+            let ((row, first, last), (next_row, next_first, next_last)) = (pair[0], pair[1]);
+            next_row == row + 1 && next_first <= last && first <= next_last
+        });
+        match rows.len() {
+            0 | 1 => Contiguity::SingleRow,
+            _ if stacked => Contiguity::StackedRows,
+            _ => Contiguity::SplitRows,
+        }
+    }
+
+    /// Indica si los asientos quedan juntos: en una fila o uno detrás del otro.
+    pub fn is_together(self) -> bool {
+        matches!(self, Contiguity::SingleRow | Contiguity::StackedRows)
+    }
+}
+
+/// Buscador de sugerencias de asientos con las reglas configuradas en el servidor.
+pub struct Allocator {
+    orphan_rule: OrphanRule,
+    distancing: Distancing,
+}
+
+/// Bloque de asientos que podría sugerirse, con su puntuación.
+#[derive(Debug, Clone)]
+struct Candidate {
+    seats: Vec<SeatKey>,
+    score: f32,
+    /// Precio total de los asientos.
+    price: f32,
+}
+
+/// Asiento visto por el buscador: su posición y si puede ofrecerse.
+#[derive(Debug, Clone, Copy)]
+struct SeatView {
+    number: u32,
+    free: bool,
+    price: f32,
+    visibility: f32,
+    wheelchair: bool,
+}
+
+/// Sección vista por el buscador, con sus filas empezando por la fila 1.
+struct SectionView<'a> {
+    section: &'a Section,
+    rows: Vec<Vec<SeatView>>,
+}
+
+/// Valores con los que se normalizan los criterios de una categoría.
+struct Scale {
+    max_visibility: f32,
+    min_price: f32,
+}
+
+impl Allocator {
+    pub fn new(orphan_rule: OrphanRule, distancing: Distancing) -> Self {
+        Allocator {
+            orphan_rule,
+            distancing,
+        }
+    }
+
+    /// Función para encontrar las mejores sugerencias de asientos para una categoría.
+    ///
+    /// Se puntúan los bloques libres de la categoría según `SCORE_WEIGHTS` y se retornan hasta
+    /// `request.max_suggestions` bloques sin asientos en común, primero los de las secciones
+    /// preferidas y luego del mejor al peor. Si la categoría no tiene lugar suficiente y el
+    /// pedido lo permite, se busca en las categorías inferiores según la cadena de descenso.
+    /// Si el pedido incluye mejoras, se agregan hasta `UPGRADE_SUGGESTIONS` bloques de la
+    /// categoría superior a la pedida. Con un código de preventa solo se buscan las secciones
+    /// que el código habilita, también al descender o al ofrecer mejoras.
+    pub fn find_seats_suggestions_by_category(
+        &self,
+        request: &SeatRequest,
+        venue: &Venue,
+        seats: &SeatMap,
+    ) -> Vec<Suggestion> {
+        if request.seats_amount == 0 {
+            return Vec::new();
+        }
+
+        let seats_guard = seats.lock().unwrap();
+        let search = Search {
+            request,
+            venue,
+            seats: &seats_guard,
+            max_visibility: venue
+                .sections
+                .iter()
+                .map(|section| section.visibility)
+                .fold(0.0, f32::max),
+            orphan_rule: self.orphan_rule,
+            distancing: self.distancing,
+        };
+
+        let mut suggestions = Vec::new();
+        let mut current = Some(request.category.as_str());
+        while let Some(category) = current {
+            let found = search.in_category(category, request.max_suggestions, false);
+            if !found.is_empty() {
+                suggestions = found;
+                break;
+            }
+
+            if !request.allow_downgrade {
+                break;
+            }
+            current = venue.lower_category(category).map(|c| c.id.as_str());
+        }
+
+        // Las mejoras se ofrecen junto a las sugerencias, para que el cliente pueda comparar
+        if request.include_upgrades && !suggestions.is_empty() {
+            if let Some(higher) = venue.higher_category(&request.category) {
+                suggestions.extend(search.in_category(&higher.id, UPGRADE_SUGGESTIONS, true));
+            }
+        }
+
+        suggestions
+    }
+}
+
+/// Datos de una búsqueda que no cambian entre categorías.
+struct Search<'a> {
+    request: &'a SeatRequest,
+    venue: &'a Venue,
+    seats: &'a HashMap<SeatKey, Seat>,
+    max_visibility: f32,
+    orphan_rule: OrphanRule,
+    distancing: Distancing,
+}
+
+impl Search<'_> {
+    /// Busca hasta `limit` bloques en una sola categoría.
+    fn in_category(&self, category: &str, limit: usize, upgrade: bool) -> Vec<Suggestion> {
+        let sections: Vec<SectionView> = self
+            .venue
+            .sections
+            .iter()
+            .filter(|section| {
+                section.category == category
+                    && !self.request.excluded_sections.contains(&section.id)
+                    && self
+                        .request
+                        .presale
+                        .as_ref()
+                        .is_none_or(|code| code.unlocks(section))
+            })
+            .map(|section| self.section_view(section))
+            .collect();
+
+        best_candidates(
+            self.request,
+            &sections,
+            self.max_visibility,
+            self.orphan_rule,
+            limit,
+        )
+        .into_iter()
+        .map(|seats| Suggestion {
+            seats,
+            category: category.to_string(),
+            upgrade,
+        })
+        .collect()
+    }
+
+    /// Arma la vista de una sección a partir del mapa de asientos. Se consideran ocupados
+    /// los asientos que falten en el mapa, los que el pedido no pueda ocupar y los que
+    /// queden dentro de la separación de otro grupo en el modo de distanciamiento.
+    fn section_view<'a>(&self, section: &'a Section) -> SectionView<'a> {
+        let rows = section
+            .rows
+            .iter()
+            .enumerate()
+            .map(|(index, &seat_count)| {
+                let row = index as u32 + 1;
+                (1..=seat_count)
+                    .map(|number| {
+                        let key = (section.id.clone(), row, number);
+                        match self.seats.get(&key) {
+                            Some(seat) => SeatView {
+                                number,
+                                free: seat.status.is_free()
+                                    && self.request.can_take(&seat.attributes)
+                                    && !self.distancing.is_crowded(self.seats, &key),
+                                price: seat.price,
+                                visibility: if seat.attributes.restricted_view {
+                                    seat.visibility * RESTRICTED_VIEW_FACTOR
+                                } else {
+                                    seat.visibility
+                                },
+                                wheelchair: seat.attributes.wheelchair,
+                            },
+                            None => SeatView {
+                                number,
+                                free: false,
+                                price: section.price,
+                                visibility: section.visibility,
+                                wheelchair: false,
+                            },
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        SectionView { section, rows }
+    }
+}
+
+/// Función auxiliar para elegir hasta `limit` bloques de una categoría sin asientos repetidos.
+fn best_candidates(
+    request: &SeatRequest,
+    sections: &[SectionView],
+    max_visibility: f32,
+    orphan_rule: OrphanRule,
+    limit: usize,
+) -> Vec<Vec<SeatKey>> {
+    let seats_amount = request.seats_amount as usize;
+    let min_price = sections
+        .iter()
+        .flat_map(|view| view.rows.iter().flatten())
+        .filter(|seat| seat.free)
+        .map(|seat| seat.price)
+        .fold(f32::INFINITY, f32::min);
+    if !min_price.is_finite() {
+        return Vec::new();
+    }
+    let scale = Scale {
+        max_visibility,
+        min_price,
+    };
+
+    let mut candidates = Vec::new();
+    for view in sections {
+        candidates.extend(row_blocks(seats_amount, view, &scale));
+        // Los acompañantes deben quedar junto a los espacios accesibles, en la misma fila
+        if seats_amount > 1 && !request.is_accessible() {
+            candidates.extend(split_blocks(seats_amount, view, &scale));
+        }
+    }
+    if request.is_accessible() {
+        let wheelchair_spaces = request.wheelchair_spaces as usize;
+        candidates
+            .retain(|candidate| accessible_seats(&candidate.seats, sections) == wheelchair_spaces);
+    }
+    let within_budget = |candidate: &Candidate| {
+        request
+            .max_total_price
+            .is_none_or(|limit| candidate.price <= limit)
+    };
+    candidates.retain(within_budget);
+    if request.together {
+        candidates.retain(|candidate| Contiguity::of(&candidate.seats).is_together());
+    } else if candidates.is_empty() && !request.is_accessible() {
+        // Si ninguna sección alcanza por sí sola, se combinan asientos de varias secciones
+        candidates = combined_blocks(seats_amount, sections, &scale);
+        candidates.retain(within_budget);
+    }
+
+    // Los bloques que dejan asientos sueltos solo se ofrecen si no hay otra opción
+    let max_gap = orphan_rule.max_gap();
+    if max_gap > 0 {
+        let (clean, orphaning): (Vec<Candidate>, Vec<Candidate>) = candidates
+            .into_iter()
+            .partition(|candidate| !leaves_orphans(&candidate.seats, sections, max_gap));
+        candidates = if clean.is_empty() { orphaning } else { clean };
+    }
+
+    candidates.sort_by(|a, b| {
+        request
+            .is_preferred(&b.seats)
+            .cmp(&request.is_preferred(&a.seats))
+            .then_with(|| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal))
+            .then_with(|| a.seats.cmp(&b.seats))
+    });
+
+    let mut taken: HashSet<SeatKey> = HashSet::new();
+    let mut suggestions = Vec::new();
+    for candidate in candidates {
+        if candidate.seats.iter().any(|seat| taken.contains(seat)) {
+            continue;
+        }
+        taken.extend(candidate.seats.iter().cloned());
+        suggestions.push(candidate.seats);
+        if suggestions.len() == limit {
+            break;
+        }
+    }
+    suggestions
+}
+
+/// Función auxiliar para obtener todos los bloques de asientos libres consecutivos de una fila.
+fn row_blocks(seats_amount: usize, view: &SectionView, scale: &Scale) -> Vec<Candidate> {
+    let mut candidates = Vec::new();
+    for (index, row) in view.rows.iter().enumerate() {
+        if row.len() < seats_amount {
+            continue;
+        }
+        for window in row.windows(seats_amount) {
+            if window.iter().all(|seat| seat.free) {
+                let picked: Vec<(u32, SeatView)> = window
+                    .iter()
+                    .map(|seat| (index as u32 + 1, *seat))
+                    .collect();
+                candidates.push(candidate(view, &picked, scale));
+            }
+        }
+    }
+    candidates
+}
+
+/// Función auxiliar para armar bloques divididos en filas consecutivas de una sección.
+/// Desde cada fila se toma el tramo libre más largo de cada fila siguiente, lo más
+/// centrado posible, hasta completar la cantidad pedida.
+fn split_blocks(seats_amount: usize, view: &SectionView, scale: &Scale) -> Vec<Candidate> {
+    let mut candidates = Vec::new();
+    for start in 0..view.rows.len() {
+        let mut picked: Vec<(u32, SeatView)> = Vec::new();
+        for (index, row) in view.rows.iter().enumerate().skip(start) {
+            let missing = seats_amount - picked.len();
+            let Some(run) = longest_free_run(row) else {
+                break;
+            };
+            let take = missing.min(run.len());
+            let offset = (run.len() - take) / 2;
+            picked.extend(
+                run[offset..offset + take]
+                    .iter()
+                    .map(|seat| (index as u32 + 1, *seat)),
+            );
+            if picked.len() == seats_amount {
+                break;
+            }
+        }
+        // Los bloques de una sola fila ya se consideraron como tramos consecutivos
+        let spans_rows = picked.first().map(|(row, _)| *row) != picked.last().map(|(row, _)| *row);
+        if picked.len() == seats_amount && spans_rows {
+            candidates.push(candidate(view, &picked, scale));
+        }
+    }
+    candidates
+}
+
+/// Función auxiliar para obtener el tramo de asientos libres consecutivos más largo de una fila.
+/// Entre tramos igual de largos se prefiere el más cercano al centro.
+fn longest_free_run(row: &[SeatView]) -> Option<&[SeatView]> {
+    let center = (row.len() as f32 + 1.0) / 2.0;
+    row.split(|seat| !seat.free)
+        .filter(|run| !run.is_empty())
+        .max_by(|a, b| {
+            let distance = |run: &[SeatView]| {
+                let middle = (run[0].number + run[run.len() - 1].number) as f32 / 2.0;
+                (middle - center).abs()
+            };
+            a.len().cmp(&b.len()).then_with(|| {
+                distance(b)
+                    .partial_cmp(&distance(a))
+                    .unwrap_or(Ordering::Equal)
+            })
+        })
+}
+
+/// Función auxiliar para combinar asientos de varias secciones cuando ninguna alcanza sola.
+/// Las secciones se recorren de la mejor a la peor puntuada y, dentro de cada una, los
+/// asientos libres se toman fila por fila para que los grupos queden juntos.
+fn combined_blocks(seats_amount: usize, sections: &[SectionView], scale: &Scale) -> Vec<Candidate> {
+    // Cada asiento libre se puntúa por separado, como un bloque de un solo asiento
+    let mut ranked: Vec<(f32, Vec<Candidate>)> = sections
+        .iter()
+        .map(|view| {
+            let free: Vec<Candidate> = view
+                .rows
+                .iter()
+                .enumerate()
+                .flat_map(|(index, row)| {
+                    let row_number = index as u32 + 1;
+                    row.iter()
+                        .filter(|seat| seat.free)
+                        .map(move |seat| Candidate {
+                            seats: vec![(view.section.id.clone(), row_number, seat.number)],
+                            score: seat_score(view, row_number, seat, scale),
+                            price: seat.price,
+                        })
+                })
+                .collect();
+            let average = free.iter().map(|seat| seat.score).sum::<f32>() / free.len() as f32;
+            (average, free)
+        })
+        .filter(|(_, free)| !free.is_empty())
+        .collect();
+    ranked.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(Ordering::Equal));
+
+    let free: Vec<Candidate> = ranked.into_iter().flat_map(|(_, free)| free).collect();
+    free.chunks_exact(seats_amount)
+        .map(|chunk| {
+            let mut seats: Vec<SeatKey> =
+                chunk.iter().flat_map(|seat| seat.seats.clone()).collect();
+            seats.sort();
+            let score = chunk.iter().map(|seat| seat.score).sum::<f32>() / seats_amount as f32
+                + SCORE_WEIGHTS.contiguity * contiguity(&seats);
+            let price = chunk.iter().map(|seat| seat.price).sum();
+            Candidate {
+                seats,
+                score,
+                price,
+            }
+        })
+        .collect()
+}
+
+/// Función auxiliar para contar los espacios para silla de ruedas de un bloque.
+fn accessible_seats(seats: &[SeatKey], sections: &[SectionView]) -> usize {
+    seats
+        .iter()
+        .filter(|(section, row, number)| {
+            sections
+                .iter()
+                .find(|view| &view.section.id == section)
+                .and_then(|view| view.rows.get(*row as usize - 1))
+                .and_then(|row| row.get(*number as usize - 1))
+                .is_some_and(|seat| seat.wheelchair)
+        })
+        .count()
+}
+
+/// Función auxiliar para saber si tomar un bloque ordenado deja junto a él tramos de
+/// `max_gap` asientos libres o menos, que difícilmente se vendan después.
+fn leaves_orphans(seats: &[SeatKey], sections: &[SectionView], max_gap: usize) -> bool {
+    let mut rows: Vec<(&str, u32)> = seats
+        .iter()
+        .map(|(section, row, _)| (section.as_str(), *row))
+        .collect();
+    rows.dedup();
+
+    rows.into_iter().any(|(section, row)| {
+        let Some(view) = sections.iter().find(|view| view.section.id == section) else {
+            return false;
+        };
+        let taken: Vec<u32> = seats
+            .iter()
+            .filter(|(s, r, _)| s == section && *r == row)
+            .map(|(_, _, number)| *number)
+            .collect();
+        let row_seats = &view.rows[row as usize - 1];
+
+        // Recorrer los tramos que quedan libres y ver si alguno corto toca el bloque
+        let mut start = 0;
+        while start < row_seats.len() {
+            let is_left = |seat: &SeatView| seat.free && !taken.contains(&seat.number);
+            if !is_left(&row_seats[start]) {
+                start += 1;
+                continue;
+            }
+            let mut end = start;
+            while end < row_seats.len() && is_left(&row_seats[end]) {
+                end += 1;
+            }
+            let touches_block = (start > 0 && taken.contains(&row_seats[start - 1].number))
+                || (end < row_seats.len() && taken.contains(&row_seats[end].number));
+            if end - start <= max_gap && touches_block {
+                return true;
+            }
+            start = end;
+        }
+        false
+    })
+}
+
+/// Función auxiliar para puntuar un bloque de asientos de una sección.
+fn candidate(view: &SectionView, picked: &[(u32, SeatView)], scale: &Scale) -> Candidate {
+    let mut seats: Vec<SeatKey> = picked
+        .iter()
+        .map(|(row, seat)| (view.section.id.clone(), *row, seat.number))
+        .collect();
+    seats.sort();
+
+    let seat_scores: f32 = picked
+        .iter()
+        .map(|(row, seat)| seat_score(view, *row, seat, scale))
+        .sum();
+    let score = seat_scores / picked.len() as f32 + SCORE_WEIGHTS.contiguity * contiguity(&seats);
+    let price = picked.iter().map(|(_, seat)| seat.price).sum();
+    Candidate {
+        seats,
+        score,
+        price,
+    }
+}
+
+/// Función auxiliar para puntuar un asiento según todos los criterios salvo la contigüidad.
+fn seat_score(view: &SectionView, row: u32, seat: &SeatView, scale: &Scale) -> f32 {
+    let visibility = if scale.max_visibility > 0.0 {
+        seat.visibility / scale.max_visibility
+    } else {
+        1.0
+    };
+
+    let row_score = 1.0 - (row - 1) as f32 / view.rows.len() as f32;
+
+    let row_len = view.rows[row as usize - 1].len() as f32;
+    let center = (row_len + 1.0) / 2.0;
+    let centrality = if row_len > 1.0 {
+        1.0 - (seat.number as f32 - center).abs() / (center - 1.0)
+    } else {
+        1.0
+    };
+
+    let price = if seat.price > 0.0 {
+        scale.min_price / seat.price
+    } else {
+        1.0
+    };
+
+    SCORE_WEIGHTS.visibility * visibility
+        + SCORE_WEIGHTS.row * row_score
+        + SCORE_WEIGHTS.centrality * centrality
+        + SCORE_WEIGHTS.price * price
+}
+
+/// Función auxiliar para medir la contigüidad de un bloque ordenado: 1 si todos los asientos
+/// son consecutivos en una misma fila, y menos cuantos más grupos separados tenga.
+fn contiguity(seats: &[SeatKey]) -> f32 {
+    let groups = 1 + seats
+        .windows(2)
+        .filter(|pair| {
+            let (section, row, number) = &pair[0];
+            pair[1] != (section.clone(), *row, number + 1)
+        })
+        .count();
+    1.0 / groups as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sección de prueba con una fila por patrón: 'F' es un asiento libre y cualquier otro
+    /// carácter uno ocupado.
+    fn section(id: &str, rows: &[&str]) -> Section {
+        Section {
+            id: id.to_string(),
+            name: id.to_string(),
+            order: 1,
+            category: "General".to_string(),
+            visibility: 1.0,
+            price: 10.0,
+            rows: rows.iter().map(|row| row.len() as u32).collect(),
+            attributes: HashMap::new(),
+        }
+    }
+
+    fn view<'a>(section: &'a Section, rows: &[&str]) -> SectionView<'a> {
+        let rows = rows
+            .iter()
+            .map(|row| {
+                row.chars()
+                    .enumerate()
+                    .map(|(index, status)| SeatView {
+                        number: index as u32 + 1,
+                        free: status == 'F',
+                        price: section.price,
+                        visibility: section.visibility,
+                        wheelchair: false,
+                    })
+                    .collect()
+            })
+            .collect();
+        SectionView { section, rows }
+    }
+
+    fn request(seats_amount: u32) -> SeatRequest {
+        SeatRequest {
+            category: "General".to_string(),
+            seats_amount,
+            together: false,
+            max_suggestions: DEFAULT_SUGGESTIONS,
+            max_total_price: None,
+            preferred_sections: Vec::new(),
+            excluded_sections: Vec::new(),
+            allow_downgrade: false,
+            include_upgrades: false,
+            wheelchair_spaces: 0,
+            presale: None,
+        }
+    }
+
+    fn seats(section: &str, picked: &[(u32, u32)]) -> Vec<SeatKey> {
+        picked
+            .iter()
+            .map(|&(row, number)| (section.to_string(), row, number))
+            .collect()
+    }
+
+    #[test]
+    fn contiguity_of_one_row() {
+        assert_eq!(Contiguity::of(&[]), Contiguity::SingleRow);
+        assert_eq!(
+            Contiguity::of(&seats("A", &[(2, 5), (2, 3), (2, 4)])),
+            Contiguity::SingleRow
+        );
+        assert_eq!(
+            Contiguity::of(&seats("A", &[(2, 3), (2, 5)])),
+            Contiguity::SplitRows
+        );
+    }
+
+    #[test]
+    fn contiguity_of_several_rows() {
+        // Tramos que se superponen, uno detrás del otro
+        assert_eq!(
+            Contiguity::of(&seats("A", &[(1, 3), (1, 4), (2, 4), (2, 5)])),
+            Contiguity::StackedRows
+        );
+        // Filas seguidas pero sin superponerse
+        assert_eq!(
+            Contiguity::of(&seats("A", &[(1, 1), (1, 2), (2, 3), (2, 4)])),
+            Contiguity::SplitRows
+        );
+        // Filas no consecutivas
+        assert_eq!(
+            Contiguity::of(&seats("A", &[(1, 1), (1, 2), (3, 1), (3, 2)])),
+            Contiguity::SplitRows
+        );
+        let mut mixed = seats("A", &[(1, 1)]);
+        mixed.extend(seats("B", &[(1, 2)]));
+        assert_eq!(Contiguity::of(&mixed), Contiguity::SplitSections);
+        assert!(!Contiguity::SplitSections.is_together());
+        assert!(Contiguity::StackedRows.is_together());
+    }
+
+    #[test]
+    fn orphan_rule_looks_at_gaps_next_to_the_block() {
+        let rows = ["FFFFFF"];
+        let a = section("A", &rows);
+        let sections = [view(&a, &rows)];
+
+        // Deja el asiento 1 aislado
+        let block = seats("A", &[(1, 2), (1, 3)]);
+        assert!(leaves_orphans(&block, &sections, 1));
+        // Deja un tramo de cuatro asientos
+        let block = seats("A", &[(1, 1), (1, 2)]);
+        assert!(!leaves_orphans(&block, &sections, 2));
+        // Deja una pareja a cada lado: solo cuenta con la regla de parejas
+        let block = seats("A", &[(1, 3), (1, 4)]);
+        assert!(!leaves_orphans(&block, &sections, 1));
+        assert!(leaves_orphans(&block, &sections, 2));
+    }
+
+    #[test]
+    fn orphan_rule_ignores_gaps_left_by_other_seats() {
+        // El asiento 1 ya estaba aislado por el asiento 2 ocupado
+        let rows = ["FXFFFFFF"];
+        let a = section("A", &rows);
+        let sections = [view(&a, &rows)];
+        let block = seats("A", &[(1, 3), (1, 4), (1, 5)]);
+        assert!(!leaves_orphans(&block, &sections, 1));
+        assert!(leaves_orphans(&block, &sections, 3));
+    }
+
+    #[test]
+    fn best_candidates_prefer_front_central_blocks() {
+        let rows = ["FFFFF", "FFFFF"];
+        let a = section("A", &rows);
+        let sections = [view(&a, &rows)];
+        let found = best_candidates(&request(3), &sections, 1.0, OrphanRule::Off, 1);
+        assert_eq!(found, vec![seats("A", &[(1, 2), (1, 3), (1, 4)])]);
+    }
+
+    #[test]
+    fn best_candidates_do_not_repeat_seats() {
+        let rows = ["FFFFFF", "FFFFFF"];
+        let a = section("A", &rows);
+        let sections = [view(&a, &rows)];
+        let found = best_candidates(&request(2), &sections, 1.0, OrphanRule::Off, 4);
+        assert_eq!(found.len(), 4);
+        let mut all: Vec<&SeatKey> = found.iter().flatten().collect();
+        all.sort();
+        all.dedup();
+        assert_eq!(all.len(), 8);
+    }
+
+    #[test]
+    fn best_candidates_skip_orphaning_blocks_when_possible() {
+        let rows = ["FFFFF"];
+        let a = section("A", &rows);
+        let sections = [view(&a, &rows)];
+        let found = best_candidates(&request(2), &sections, 1.0, OrphanRule::Singles, 3);
+        let expected = [seats("A", &[(1, 1), (1, 2)]), seats("A", &[(1, 4), (1, 5)])];
+        assert_eq!(found.len(), 2);
+        assert!(found.iter().all(|block| expected.contains(block)));
+
+        // Si todos los bloques dejan asientos sueltos, igual se ofrecen
+        let rows = ["FFF"];
+        let b = section("B", &rows);
+        let sections = [view(&b, &rows)];
+        let found = best_candidates(&request(2), &sections, 1.0, OrphanRule::Singles, 3);
+        assert_eq!(found.len(), 1);
+    }
+
+    #[test]
+    fn best_candidates_respect_together_and_price() {
+        // Ninguna fila tiene tres asientos seguidos
+        let rows = ["FFXFF", "FFXFF"];
+        let a = section("A", &rows);
+        let sections = [view(&a, &rows)];
+        let mut together = request(3);
+        together.together = true;
+        let found = best_candidates(&together, &sections, 1.0, OrphanRule::Off, 3);
+        assert!(found
+            .iter()
+            .all(|block| Contiguity::of(block).is_together()));
+        assert!(!found.is_empty());
+
+        let mut cheap = request(2);
+        cheap.max_total_price = Some(15.0);
+        assert!(best_candidates(&cheap, &sections, 1.0, OrphanRule::Off, 3).is_empty());
+    }
+}
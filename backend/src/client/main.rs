@@ -0,0 +1,531 @@
+// Dependencies:
+mod protocol;
+mod seat_map;
+
+use crate::protocol::{
+    CardDetails, ClientEnvelope, ClientMessage, SeatInfo, SeatRef, SeatSuggestion,
+    ServerEnvelope, ServerMessage,
+};
+use futures_util::{SinkExt, StreamExt};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::io::{self, AsyncBufReadExt, AsyncWriteExt, BufReader, Lines, Stdin};
+use tokio::net::TcpStream;
+use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
+use tungstenite::protocol::Message;
+use url::Url;
+
+/// Server used when no URL is given on the command line
+const DEFAULT_SERVER_URL: &str = "ws://127.0.0.1:8080";
+
+/// File in the temporary directory where the last admission token is kept between runs
+const TOKEN_FILE: &str = "ticketbuddy-admission-token";
+
+/// Connection to the server that pairs every request with its response
+struct Connection {
+    ws_stream: WebSocketStream<MaybeTlsStream<TcpStream>>,
+    next_request: u64,
+}
+
+impl Connection {
+    /// Wait for the next message pushed by the server
+    async fn receive(&mut self) -> Result<ServerEnvelope, String> {
+        loop {
+            match self.ws_stream.next().await {
+                Some(Ok(Message::Text(text))) => {
+                    return serde_json::from_str(&text)
+                        .map_err(|e| format!("Unexpected message from the server: {}", e));
+                }
+                Some(Ok(Message::Close(_))) | None => {
+                    return Err("The server closed the connection".to_string())
+                }
+                Some(Ok(_)) => continue,
+                Some(Err(e)) => return Err(format!("Connection error: {}", e)),
+            }
+        }
+    }
+
+    /// Send a request and wait for its response, reporting any notices received meanwhile
+    async fn request(&mut self, message: ClientMessage) -> Result<ServerMessage, String> {
+        self.next_request += 1;
+        let request_id = self.next_request.to_string();
+        let text = serde_json::to_string(&ClientEnvelope {
+            request_id: request_id.clone(),
+            message: &message,
+        })
+        .map_err(|e| e.to_string())?;
+        self.ws_stream
+            .send(Message::Text(text))
+            .await
+            .map_err(|e| format!("Failed to send message: {}", e))?;
+
+        loop {
+            let envelope = self.receive().await?;
+            if envelope.request_id.as_deref() == Some(request_id.as_str()) {
+                return Ok(envelope.message);
+            }
+            if let ServerMessage::HoldExpired { seats } = envelope.message {
+                println!("Your hold expired for: {}", seat_list(&seats));
+            }
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    // Service URL
+    let url = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| DEFAULT_SERVER_URL.to_string());
+    let url = match Url::parse(&url) {
+        Ok(url) => url,
+        Err(e) => {
+            eprintln!("Invalid server URL {}: {}", url, e);
+            std::process::exit(1);
+        }
+    };
+
+    // Try to connect to the server
+    let (ws_stream, _) = match connect_async(url.clone()).await {
+        Ok(connection) => connection,
+        Err(e) => {
+            eprintln!("Failed to connect to {}: {}", url, e);
+            std::process::exit(1);
+        }
+    };
+    let mut connection = Connection {
+        ws_stream,
+        next_request: 0,
+    };
+
+    println!("Connected to the server");
+
+    if let Err(e) = run(&mut connection).await {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+
+    // Close the connection
+    let _ = connection.ws_stream.close(None).await;
+}
+
+/// Walk the user through requesting, accepting and paying for seats
+async fn run(connection: &mut Connection) -> Result<(), String> {
+    let mut input = BufReader::new(io::stdin()).lines();
+
+    // The server greets every client with the events on sale, after the waiting room if any
+    let mut saved_token = saved_token();
+    let mut events = loop {
+        match connection.receive().await?.message {
+            ServerMessage::Events { events } => break events,
+            ServerMessage::QueuePosition { position, eta_secs } => {
+                println!(
+                    "You are number {} in line (about {} seconds)",
+                    position, eta_secs
+                );
+                // A token from an earlier connection skips the line while it is valid
+                if let Some(token) = saved_token.take() {
+                    match connection
+                        .request(ClientMessage::PresentToken { token })
+                        .await?
+                    {
+                        ServerMessage::Admitted { token, expires_at } => {
+                            println!("Your earlier admission is still valid");
+                            save_token(&token, expires_at);
+                        }
+                        response => {
+                            report(response);
+                            println!("Please wait for your turn");
+                            forget_token();
+                        }
+                    }
+                }
+            }
+            ServerMessage::Admitted { token, expires_at } => {
+                println!("It's your turn!");
+                save_token(&token, expires_at);
+            }
+            _ => {}
+        }
+    };
+    if events.is_empty() {
+        return Err("There are no events on sale".to_string());
+    }
+    let event = if events.len() == 1 {
+        events.remove(0)
+    } else {
+        loop {
+            println!("Please select an event:");
+            for (index, event) in events.iter().enumerate() {
+                println!(
+                    "{}: {} ({})",
+                    index + 1,
+                    event.name,
+                    event.status.replace('_', " ")
+                );
+            }
+            let Some(choice) = prompt(&mut input, "> ").await? else {
+                return Ok(());
+            };
+            match choice.parse::<usize>() {
+                Ok(n) if (1..=events.len()).contains(&n) => break events.remove(n - 1),
+                _ => println!("Invalid selection"),
+            }
+        }
+    };
+    let venue = &event.venue;
+    println!("Welcome to {} at {}", event.name, venue.name);
+
+    // Presale purchases need a code that unlocks the seats
+    let access_code = if event.status == "presale" {
+        let Some(code) = prompt(&mut input, "Presale access code: ").await? else {
+            return Ok(());
+        };
+        Some(code)
+    } else {
+        None
+    };
+
+    loop {
+        // Show the user the available seat categories
+        println!();
+        println!("Please select a seat category (m for the seat map, q to quit):");
+        for (index, category) in venue.categories.iter().enumerate() {
+            println!("{}: {}", index + 1, category.name);
+        }
+        let Some(choice) = prompt(&mut input, "> ").await? else {
+            return Ok(());
+        };
+        if choice == "q" {
+            return Ok(());
+        }
+        if choice == "m" {
+            let Some(picked) = seat_map::show(connection, &event).await? else {
+                continue;
+            };
+            if picked.is_empty() {
+                println!("No seats were picked");
+                continue;
+            }
+            let seats: Vec<SeatRef> = picked
+                .into_iter()
+                .map(|(section, row, number)| SeatRef {
+                    section,
+                    row,
+                    number,
+                })
+                .collect();
+            let hold = |wheelchair_spaces| ClientMessage::HoldSeats {
+                event: event.id.clone(),
+                seats: seats.clone(),
+                access_code: access_code.clone(),
+                wheelchair_spaces,
+            };
+            let mut response = connection.request(hold(0)).await?;
+            // Wheelchair spaces and companion seats need the number of wheelchair users
+            if matches!(&response, ServerMessage::Error { code, .. } if code == "accessible_seat") {
+                report(response);
+                let Some(spaces) =
+                    prompt(&mut input, "Wheelchair spaces needed (Enter to cancel): ").await?
+                else {
+                    return Ok(());
+                };
+                let Ok(spaces) = spaces.parse::<u32>() else {
+                    println!("Please enter a number");
+                    continue;
+                };
+                response = connection.request(hold(spaces)).await?;
+            }
+            let held = matches!(response, ServerMessage::SeatsHeld { .. });
+            report(response);
+            if held && !pay(connection, &mut input).await? {
+                return Ok(());
+            }
+            continue;
+        }
+        let Some(category) = choice
+            .parse::<usize>()
+            .ok()
+            .and_then(|n| n.checked_sub(1))
+            .and_then(|index| venue.categories.get(index))
+        else {
+            println!("Invalid selection");
+            continue;
+        };
+
+        let Some(seat_count) = prompt(&mut input, "How many seats would you like? ").await? else {
+            return Ok(());
+        };
+        let Ok(seat_count) = seat_count.parse::<u32>() else {
+            println!("Please enter a number");
+            continue;
+        };
+        let together = if seat_count > 1 {
+            let Some(answer) = prompt(&mut input, "Only seats together? (y/N) ").await? else {
+                return Ok(());
+            };
+            answer.eq_ignore_ascii_case("y")
+        } else {
+            false
+        };
+        let Some(wheelchair_spaces) =
+            prompt(&mut input, "Wheelchair spaces needed (Enter for none): ").await?
+        else {
+            return Ok(());
+        };
+        let wheelchair_spaces = if wheelchair_spaces.is_empty() {
+            0
+        } else {
+            match wheelchair_spaces.parse::<u32>() {
+                Ok(spaces) => spaces,
+                Err(_) => {
+                    println!("Please enter a number");
+                    continue;
+                }
+            }
+        };
+
+        let response = connection
+            .request(ClientMessage::RequestSeats {
+                event: event.id.clone(),
+                category: category.id.clone(),
+                seat_count,
+                together,
+                include_upgrades: true,
+                wheelchair_spaces,
+                access_code: access_code.clone(),
+            })
+            .await?;
+        let suggestions = match response {
+            ServerMessage::Suggestions { suggestions } if !suggestions.is_empty() => suggestions,
+            ServerMessage::Suggestions { .. } => {
+                println!("No seats available for that request");
+                continue;
+            }
+            other => {
+                report(other);
+                continue;
+            }
+        };
+
+        if !choose_and_pay(connection, &mut input, &suggestions).await? {
+            return Ok(());
+        }
+    }
+}
+
+/// Show the suggestions, let the user pick one and pay for it.
+/// Returns `false` if the user closed the input.
+async fn choose_and_pay(
+    connection: &mut Connection,
+    input: &mut Lines<BufReader<Stdin>>,
+    suggestions: &[SeatSuggestion],
+) -> Result<bool, String> {
+    println!("Suggested seats (Section - Row - Number):");
+    for suggestion in suggestions {
+        println!(
+            "{}: {} | Total: {:.2} | {}",
+            suggestion.suggestion_number,
+            suggestion
+                .seats
+                .iter()
+                .map(seat_label)
+                .collect::<Vec<_>>()
+                .join(", "),
+            suggestion.total_price,
+            contiguity_label(&suggestion.contiguity)
+        );
+        if suggestion.upgrade {
+            match suggestion.price_difference {
+                Some(difference) => println!(
+                    "   Upgrade to {} for {:+.2}",
+                    suggestion.category, difference
+                ),
+                None => println!("   Upgrade to {}", suggestion.category),
+            }
+        }
+    }
+
+    let Some(choice) = prompt(
+        input,
+        "Choose a suggestion number, or r to reject them all: ",
+    )
+    .await?
+    else {
+        return Ok(false);
+    };
+    let Ok(suggestion_number) = choice.parse::<usize>() else {
+        let response = connection.request(ClientMessage::RejectSuggestions).await?;
+        report(response);
+        return Ok(true);
+    };
+
+    let response = connection
+        .request(ClientMessage::ChooseSuggestion { suggestion_number })
+        .await?;
+    let accepted = matches!(response, ServerMessage::SuggestionAccepted { .. });
+    report(response);
+    if !accepted {
+        return Ok(true);
+    }
+
+    pay(connection, input).await
+}
+
+/// Pay for the seats held for this client; a declined payment releases them on the server.
+/// Returns `false` if the user closed the input.
+async fn pay(
+    connection: &mut Connection,
+    input: &mut Lines<BufReader<Stdin>>,
+) -> Result<bool, String> {
+    let Some(card) = read_card(input).await? else {
+        return Ok(false);
+    };
+    let customer = prompt(input, "Customer reference (optional): ")
+        .await?
+        .filter(|customer| !customer.is_empty());
+
+    let response = connection
+        .request(ClientMessage::Pay { card, customer })
+        .await?;
+    report(response);
+    Ok(true)
+}
+
+/// Ask for the card details. Returns `None` if the input was closed.
+async fn read_card(input: &mut Lines<BufReader<Stdin>>) -> Result<Option<CardDetails>, String> {
+    let mut fields = Vec::new();
+    for label in [
+        "Card number: ",
+        "Card holder: ",
+        "Expiry (MM/YY): ",
+        "CVC: ",
+    ] {
+        match prompt(input, label).await? {
+            Some(value) => fields.push(value),
+            None => return Ok(None),
+        }
+    }
+    let mut fields = fields.into_iter();
+    Ok(Some(CardDetails {
+        number: fields.next().unwrap_or_default(),
+        holder: fields.next().unwrap_or_default(),
+        expiry: fields.next().unwrap_or_default(),
+        cvc: fields.next().unwrap_or_default(),
+    }))
+}
+
+/// Print a response from the server
+fn report(message: ServerMessage) {
+    match message {
+        ServerMessage::SuggestionAccepted { suggestion_number } => {
+            println!(
+                "Suggestion {} accepted; the seats are held for you",
+                suggestion_number
+            )
+        }
+        ServerMessage::SuggestionsRejected => println!("Suggestions rejected"),
+        ServerMessage::SeatsHeld { seats, total_price } => {
+            println!("Seats held for you (Section - Row - Number):");
+            for seat in &seats {
+                println!("  {}", seat_label(seat));
+            }
+            println!("Total: {:.2}", total_price);
+        }
+        ServerMessage::PaymentAccepted { total_price, order } => {
+            println!(
+                "Payment accepted: {:.2}. Order {} for {} (access key {})",
+                total_price, order.id, order.customer, order.access_key
+            );
+            for ticket in order.tickets {
+                println!(
+                    "  Ticket {}: {}-{}-{} ({:.2})",
+                    ticket.id, ticket.section, ticket.row, ticket.number, ticket.price
+                );
+            }
+        }
+        ServerMessage::PaymentDeclined { reason } => {
+            println!("Payment declined: {}. The seats were released", reason)
+        }
+        ServerMessage::Error { code, message } => println!("Error ({}): {}", code, message),
+        other => println!("Unexpected response: {:?}", other),
+    }
+}
+
+/// Path of the file that keeps the admission token between runs
+fn token_path() -> PathBuf {
+    std::env::temp_dir().join(TOKEN_FILE)
+}
+
+/// Admission token kept by an earlier run, if it has not expired yet
+fn saved_token() -> Option<String> {
+    let contents = std::fs::read_to_string(token_path()).ok()?;
+    let (token, expires_at) = contents.trim().split_once(' ')?;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs();
+    (expires_at.parse::<u64>().ok()? > now).then(|| token.to_string())
+}
+
+/// Keep the admission token so the next run can skip the waiting room
+fn save_token(token: &str, expires_at: u64) {
+    if let Err(e) = std::fs::write(token_path(), format!("{} {}\n", token, expires_at)) {
+        eprintln!("Could not save the admission token: {}", e);
+    }
+}
+
+/// Drop a saved admission token the server no longer accepts
+fn forget_token() {
+    let _ = std::fs::remove_file(token_path());
+}
+
+/// Show a prompt and read one trimmed line. Returns `None` at the end of the input.
+async fn prompt(
+    input: &mut Lines<BufReader<Stdin>>,
+    label: &str,
+) -> Result<Option<String>, String> {
+    let mut stdout = io::stdout();
+    stdout
+        .write_all(label.as_bytes())
+        .await
+        .map_err(|e| e.to_string())?;
+    stdout.flush().await.map_err(|e| e.to_string())?;
+    input
+        .next_line()
+        .await
+        .map(|line| line.map(|line| line.trim().to_string()))
+        .map_err(|e| format!("Failed to read line: {}", e))
+}
+
+/// Seat position and price, followed by its special features if it has any
+fn seat_label(seat: &SeatInfo) -> String {
+    let labels = seat.attributes.labels();
+    let mut label = format!(
+        "{}-{}-{} ({:.2}",
+        seat.section, seat.row, seat.number, seat.price
+    );
+    if !labels.is_empty() {
+        label.push_str(", ");
+        label.push_str(&labels.join(", "));
+    }
+    label.push(')');
+    label
+}
+
+/// Describe how the seats of a suggestion are spread
+fn contiguity_label(contiguity: &str) -> &str {
+    match contiguity {
+        "single_row" => "together in one row",
+        "stacked_rows" => "together in consecutive rows",
+        "split_rows" => "split within the section",
+        "split_sections" => "split across sections",
+        other => other,
+    }
+}
+
+/// Format seats as `Section-Row-Number`
+fn seat_list(seats: &[SeatRef]) -> String {
+    seats
+        .iter()
+        .map(|seat| format!("{}-{}-{}", seat.section, seat.row, seat.number))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -0,0 +1,232 @@
+// Client-side copy of the messages exchanged with the server (see the server's protocol.rs)
+use serde::{Deserialize, Serialize};
+
+/// Messages the client sends, mirroring `protocol::ClientMessage` on the server
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    RequestSeats {
+        event: String,
+        category: String,
+        seat_count: u32,
+        together: bool,
+        include_upgrades: bool,
+        /// Wheelchair spaces; the other seats are for adjacent companions
+        wheelchair_spaces: u32,
+        /// Needed while the event is in presale
+        access_code: Option<String>,
+    },
+    ChooseSuggestion {
+        suggestion_number: usize,
+    },
+    RejectSuggestions,
+    HoldSeats {
+        event: String,
+        seats: Vec<SeatRef>,
+        access_code: Option<String>,
+        /// Needed to hold wheelchair spaces and companion seats
+        wheelchair_spaces: u32,
+    },
+    GetState {
+        event: String,
+    },
+    /// Leave the waiting room with a token received in an earlier connection
+    PresentToken {
+        token: String,
+    },
+    Pay {
+        card: CardDetails,
+        customer: Option<String>,
+    },
+}
+
+#[derive(Debug, Serialize)]
+pub struct ClientEnvelope<'a> {
+    pub request_id: String,
+    #[serde(flatten)]
+    pub message: &'a ClientMessage,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CardDetails {
+    pub number: String,
+    pub holder: String,
+    pub expiry: String,
+    pub cvc: String,
+}
+
+/// Messages the client understands, mirroring `protocol::ServerMessage` on the server.
+/// Only the fields the client shows are declared; the rest are ignored.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    /// Place in the waiting room and estimated seconds until admission
+    QueuePosition {
+        position: u64,
+        eta_secs: u64,
+    },
+    /// The session left the waiting room; the token lets it skip the line until it expires
+    Admitted {
+        token: String,
+        /// Seconds since the Unix epoch
+        expires_at: u64,
+    },
+    Events {
+        events: Vec<EventInfo>,
+    },
+    SeatStates {
+        seats: Vec<SeatState>,
+    },
+    SeatUpdates {
+        seats: Vec<SeatState>,
+    },
+    Suggestions {
+        suggestions: Vec<SeatSuggestion>,
+    },
+    SuggestionAccepted {
+        suggestion_number: usize,
+    },
+    SuggestionsRejected,
+    SeatsHeld {
+        seats: Vec<SeatInfo>,
+        total_price: f32,
+    },
+    HoldExpired {
+        seats: Vec<SeatRef>,
+    },
+    PaymentAccepted {
+        total_price: f32,
+        order: OrderInfo,
+    },
+    PaymentDeclined {
+        reason: String,
+    },
+    Error {
+        code: String,
+        message: String,
+    },
+    /// Messages the client does not show (venue of the first event, order lookups)
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ServerEnvelope {
+    pub request_id: Option<String>,
+    #[serde(flatten)]
+    pub message: ServerMessage,
+}
+
+/// Show on sale, with the layout of its venue
+#[derive(Debug, Deserialize)]
+pub struct EventInfo {
+    pub id: String,
+    pub name: String,
+    /// Sales status: draft, presale, on_sale, paused, sold_out or closed
+    pub status: String,
+    pub venue: VenueInfo,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VenueInfo {
+    pub name: String,
+    pub categories: Vec<CategoryInfo>,
+    pub sections: Vec<SectionInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CategoryInfo {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SectionInfo {
+    pub id: String,
+    pub category: String,
+}
+
+/// State of one seat; `booked` is 'F' free, 'R' held, 'B' booked, 'X' blocked
+/// or 'D' distancing buffer
+#[derive(Debug, Deserialize)]
+pub struct SeatState {
+    pub section: String,
+    pub row: u32,
+    pub number: u32,
+    pub booked: char,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SeatSuggestion {
+    pub suggestion_number: usize,
+    pub seats: Vec<SeatInfo>,
+    pub total_price: f32,
+    /// `single_row`, `stacked_rows`, `split_rows` or `split_sections`
+    pub contiguity: String,
+    pub category: String,
+    /// Offer from the category above the requested one
+    pub upgrade: bool,
+    /// Extra cost of an upgrade over the first regular suggestion
+    pub price_difference: Option<f32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SeatInfo {
+    pub section: String,
+    pub row: u32,
+    pub number: u32,
+    pub price: f32,
+    #[serde(default)]
+    pub attributes: SeatAttributes,
+}
+
+/// Special features of a seat defined in the venue layout
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct SeatAttributes {
+    pub wheelchair: bool,
+    pub companion: bool,
+    pub restricted_view: bool,
+    pub aisle: bool,
+}
+
+impl SeatAttributes {
+    /// Short names of the features the seat has
+    pub fn labels(&self) -> Vec<&'static str> {
+        [
+            (self.wheelchair, "wheelchair"),
+            (self.companion, "companion"),
+            (self.restricted_view, "restricted view"),
+            (self.aisle, "aisle"),
+        ]
+        .into_iter()
+        .filter(|(has, _)| *has)
+        .map(|(_, label)| label)
+        .collect()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeatRef {
+    pub section: String,
+    pub row: u32,
+    pub number: u32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OrderInfo {
+    pub id: String,
+    /// Needed to look the order up from another connection
+    pub access_key: String,
+    pub customer: String,
+    pub tickets: Vec<TicketInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TicketInfo {
+    pub id: String,
+    pub section: String,
+    pub row: u32,
+    pub number: u32,
+    pub price: f32,
+}
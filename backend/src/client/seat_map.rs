@@ -0,0 +1,344 @@
+// Interactive seat map: the whole venue as a colored grid, updated live
+use crate::protocol::{ClientMessage, EventInfo, SeatState, ServerMessage, VenueInfo};
+use crate::Connection;
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{Event, EventStream, KeyCode, KeyEvent, KeyEventKind};
+use crossterm::style::{Color, Print, PrintStyledContent, Stylize};
+use crossterm::terminal::{self, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+use futures_util::StreamExt;
+use std::collections::{BTreeSet, HashMap};
+use std::io::{self, Write};
+
+/// Seat identifier: section, row and number
+pub type SeatKey = (String, u32, u32);
+
+/// Glyph drawn for every seat
+const SEAT_GLYPH: &str = "■";
+
+/// Rows and seats of one section, taken from the seat snapshot
+struct SectionLayout {
+    id: String,
+    category: String,
+    /// Seats in each row, starting at row 1
+    rows: Vec<u32>,
+}
+
+impl SectionLayout {
+    fn header(&self) -> String {
+        format!("{} ({})", self.id, self.category)
+    }
+
+    /// Columns taken by the section on screen
+    fn width(&self) -> u16 {
+        let seats = self.rows.iter().copied().max().unwrap_or(0) as usize;
+        (3 + seats * 2).max(self.header().chars().count()) as u16
+    }
+}
+
+/// What the map should do after a key press
+enum Action {
+    Continue,
+    Done,
+    Cancel,
+}
+
+/// Seat map state: layout, seat states, cursor and picked seats
+struct SeatMap {
+    venue_name: String,
+    sections: Vec<SectionLayout>,
+    states: HashMap<SeatKey, char>,
+    /// Index of the section under the cursor, plus row and seat number
+    cursor: (usize, u32, u32),
+    selected: BTreeSet<SeatKey>,
+    notice: String,
+}
+
+impl SeatMap {
+    fn new(venue: &VenueInfo, seats: Vec<SeatState>) -> Self {
+        let mut rows_by_section: HashMap<&str, Vec<u32>> = HashMap::new();
+        for seat in &seats {
+            let rows = rows_by_section.entry(seat.section.as_str()).or_default();
+            let row = seat.row as usize;
+            if rows.len() < row {
+                rows.resize(row, 0);
+            }
+            rows[row - 1] = rows[row - 1].max(seat.number);
+        }
+
+        // Sections keep the order in which the venue lists them
+        let sections = venue
+            .sections
+            .iter()
+            .filter_map(|section| {
+                rows_by_section
+                    .get(section.id.as_str())
+                    .map(|rows| SectionLayout {
+                        id: section.id.clone(),
+                        category: section.category.clone(),
+                        rows: rows.clone(),
+                    })
+            })
+            .collect();
+
+        let mut map = SeatMap {
+            venue_name: venue.name.clone(),
+            sections,
+            states: HashMap::new(),
+            cursor: (0, 1, 1),
+            selected: BTreeSet::new(),
+            notice: String::new(),
+        };
+        map.replace(seats);
+        map
+    }
+
+    /// Replace every seat state with a full snapshot
+    fn replace(&mut self, seats: Vec<SeatState>) {
+        self.states.clear();
+        self.apply(seats);
+    }
+
+    /// Apply seat changes pushed by the server
+    fn apply(&mut self, seats: Vec<SeatState>) {
+        for seat in seats {
+            let key = (seat.section, seat.row, seat.number);
+            if seat.booked != 'F' && self.selected.remove(&key) {
+                self.notice = format!("{}-{}-{} was taken by someone else", key.0, key.1, key.2);
+            }
+            self.states.insert(key, seat.booked);
+        }
+    }
+
+    fn cursor_key(&self) -> SeatKey {
+        let (section, row, number) = self.cursor;
+        (self.sections[section].id.clone(), row, number)
+    }
+
+    /// Seats in a row of a section (0 if the row does not exist)
+    fn row_len(&self, section: usize, row: u32) -> u32 {
+        self.sections[section]
+            .rows
+            .get(row as usize - 1)
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Put the cursor in a section, clamping the row and seat to what the section has
+    fn move_to(&mut self, section: usize, row: u32, number: u32) {
+        let rows = self.sections[section].rows.len() as u32;
+        let row = row.clamp(1, rows);
+        let number = number.clamp(1, self.row_len(section, row).max(1));
+        self.cursor = (section, row, number);
+    }
+
+    fn next_section(&self, section: usize) -> usize {
+        (section + 1) % self.sections.len()
+    }
+
+    fn previous_section(&self, section: usize) -> usize {
+        (section + self.sections.len() - 1) % self.sections.len()
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> Action {
+        let (section, row, number) = self.cursor;
+        self.notice.clear();
+        match key.code {
+            KeyCode::Left if number > 1 => self.move_to(section, row, number - 1),
+            KeyCode::Left => self.move_to(self.previous_section(section), row, u32::MAX),
+            KeyCode::Right if number < self.row_len(section, row) => {
+                self.move_to(section, row, number + 1)
+            }
+            KeyCode::Right => self.move_to(self.next_section(section), row, 1),
+            KeyCode::Up if row > 1 => self.move_to(section, row - 1, number),
+            KeyCode::Up => self.move_to(self.previous_section(section), u32::MAX, number),
+            KeyCode::Down if (row as usize) < self.sections[section].rows.len() => {
+                self.move_to(section, row + 1, number)
+            }
+            KeyCode::Down => self.move_to(self.next_section(section), 1, number),
+            KeyCode::Tab => self.move_to(self.next_section(section), 1, 1),
+            KeyCode::BackTab => self.move_to(self.previous_section(section), 1, 1),
+            KeyCode::Char(' ') => {
+                let key = self.cursor_key();
+                if !self.selected.remove(&key) {
+                    if self.states.get(&key) == Some(&'F') {
+                        self.selected.insert(key);
+                    } else {
+                        self.notice = "Only free seats can be picked".to_string();
+                    }
+                }
+            }
+            KeyCode::Enter => return Action::Done,
+            KeyCode::Esc | KeyCode::Char('q') => return Action::Cancel,
+            _ => {}
+        }
+        Action::Continue
+    }
+
+    fn draw(&self) -> io::Result<()> {
+        let mut out = io::stdout();
+        let (width, _) = terminal::size()?;
+        queue!(
+            out,
+            terminal::Clear(ClearType::All),
+            MoveTo(0, 0),
+            PrintStyledContent(self.venue_name.as_str().bold())
+        )?;
+
+        // Sections flow left to right and wrap when the terminal is too narrow
+        let (mut x, mut y, mut band_height) = (0u16, 2u16, 0u16);
+        for (index, section) in self.sections.iter().enumerate() {
+            let block_width = section.width();
+            if x > 0 && x + block_width > width {
+                x = 0;
+                y += band_height + 1;
+                band_height = 0;
+            }
+
+            queue!(
+                out,
+                MoveTo(x, y),
+                PrintStyledContent(section.header().bold())
+            )?;
+            for (row_index, &seats) in section.rows.iter().enumerate() {
+                let row = row_index as u32 + 1;
+                queue!(
+                    out,
+                    MoveTo(x, y + 1 + row_index as u16),
+                    Print(format!("{:>2}", row))
+                )?;
+                for number in 1..=seats {
+                    let key = (section.id.clone(), row, number);
+                    let color = if self.selected.contains(&key) {
+                        Color::Blue
+                    } else {
+                        match self.states.get(&key) {
+                            Some('F') => Color::Green,
+                            Some('R') => Color::Yellow,
+                            Some('B') => Color::Red,
+                            Some('X') => Color::DarkGrey,
+                            Some('D') => Color::Magenta,
+                            // Gaps in a row (seats missing from the snapshot)
+                            _ => Color::Black,
+                        }
+                    };
+                    let mut glyph = SEAT_GLYPH.with(color);
+                    if self.cursor == (index, row, number) {
+                        glyph = glyph.reverse();
+                    }
+                    queue!(out, Print(" "), PrintStyledContent(glyph))?;
+                }
+            }
+
+            x += block_width + 2;
+            band_height = band_height.max(section.rows.len() as u16 + 1);
+        }
+
+        let (section, row, number) = self.cursor_key();
+        let status = match self.states.get(&(section.clone(), row, number)) {
+            Some('F') => "free",
+            Some('R') => "held",
+            Some('B') => "booked",
+            Some('X') => "blocked",
+            Some('D') => "distancing buffer",
+            _ => "unknown",
+        };
+        let legend_y = y + band_height + 1;
+        queue!(
+            out,
+            MoveTo(0, legend_y),
+            PrintStyledContent(SEAT_GLYPH.green()),
+            Print(" free  "),
+            PrintStyledContent(SEAT_GLYPH.yellow()),
+            Print(" held  "),
+            PrintStyledContent(SEAT_GLYPH.red()),
+            Print(" booked  "),
+            PrintStyledContent(SEAT_GLYPH.dark_grey()),
+            Print(" blocked  "),
+            PrintStyledContent(SEAT_GLYPH.magenta()),
+            Print(" buffer  "),
+            PrintStyledContent(SEAT_GLYPH.blue()),
+            Print(" picked"),
+            MoveTo(0, legend_y + 1),
+            Print(format!(
+                "Seat {}-{}-{} ({}) | Picked: {}",
+                section,
+                row,
+                number,
+                status,
+                self.selected.len()
+            )),
+            MoveTo(0, legend_y + 2),
+            Print("Arrows/Tab move, Space picks, Enter confirms, Esc goes back"),
+            MoveTo(0, legend_y + 3),
+            Print(&self.notice),
+        )?;
+        out.flush()
+    }
+}
+
+/// Raw mode on an alternate screen, restored when dropped
+struct Screen;
+
+impl Screen {
+    fn enter() -> io::Result<Screen> {
+        terminal::enable_raw_mode()?;
+        execute!(io::stdout(), EnterAlternateScreen, Hide)?;
+        Ok(Screen)
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), Show, LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// Show the seat map until the user confirms or goes back.
+/// Returns the picked seats, or `None` if the user went back.
+pub async fn show(
+    connection: &mut Connection,
+    event: &EventInfo,
+) -> Result<Option<Vec<SeatKey>>, String> {
+    let request = ClientMessage::GetState {
+        event: event.id.clone(),
+    };
+    let seats = match connection.request(request).await? {
+        ServerMessage::SeatStates { seats } => seats,
+        other => return Err(format!("Unexpected response: {:?}", other)),
+    };
+    let mut map = SeatMap::new(&event.venue, seats);
+    if map.sections.is_empty() {
+        return Err("The venue has no seats".to_string());
+    }
+
+    let _screen = Screen::enter().map_err(|e| e.to_string())?;
+    let mut events = EventStream::new();
+    loop {
+        map.draw().map_err(|e| e.to_string())?;
+        tokio::select! {
+            event = events.next() => match event {
+                Some(Ok(Event::Key(key))) if key.kind == KeyEventKind::Press => {
+                    match map.handle_key(key) {
+                        Action::Continue => {}
+                        Action::Done => return Ok(Some(map.selected.into_iter().collect())),
+                        Action::Cancel => return Ok(None),
+                    }
+                }
+                Some(Ok(_)) => {}
+                Some(Err(e)) => return Err(e.to_string()),
+                None => return Ok(None),
+            },
+            envelope = connection.receive() => match envelope?.message {
+                ServerMessage::SeatUpdates { seats } => map.apply(seats),
+                // Sent when this client fell behind on updates
+                ServerMessage::SeatStates { seats } => map.replace(seats),
+                ServerMessage::HoldExpired { .. } => {
+                    map.notice = "Your held seats expired".to_string();
+                }
+                _ => {}
+            },
+        }
+    }
+}
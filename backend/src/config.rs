@@ -0,0 +1,149 @@
+// config.rs
+
+use crate::allocator::OrphanRule;
+use crate::seat_manager::Distancing;
+use std::env;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Tiempo que un asiento permanece reservado temporalmente si no se indica otro.
+const DEFAULT_HOLD_TTL_SECS: u64 = 300;
+
+/// Mayor duración de una reserva temporal que se acepta (una hora).
+const MAX_HOLD_TTL_SECS: u64 = 3_600;
+
+/// Base de datos SQLite usada si no se indica otra.
+const DEFAULT_DB_PATH: &str = "ticketbuddy.db";
+
+/// Duración de un token de admisión de la sala de espera si no se indica otra.
+const DEFAULT_ADMISSION_TOKEN_TTL_SECS: u64 = 900;
+
+/// Mayor duración de un token de admisión que se acepta (un día).
+const MAX_ADMISSION_TOKEN_TTL_SECS: u64 = 86_400;
+
+/// Regla de asientos sueltos usada si no se indica otra.
+const DEFAULT_ORPHAN_RULE: OrphanRule = OrphanRule::Singles;
+
+/// Mayor separación en asientos o filas que acepta el modo de distanciamiento.
+const MAX_DISTANCING: u32 = 10;
+
+/// Parámetros del servidor que se leen de variables de entorno.
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    /// Duración de una reserva temporal ('R') antes de que el asiento vuelva a estar libre.
+    pub hold_ttl: Duration,
+    /// Archivo SQLite donde se guardan los asientos, las reservas y las compras.
+    pub db_path: PathBuf,
+    /// Regla para no dejar asientos libres sueltos al sugerir bloques.
+    pub orphan_rule: OrphanRule,
+    /// Separación entre grupos de compradores; desactivada si no se indica.
+    pub distancing: Distancing,
+    /// Sesiones que la sala de espera admite por minuto; 0 la desactiva.
+    pub admissions_per_minute: u64,
+    /// Tiempo en que un token de admisión permite volver a entrar sin hacer la fila.
+    pub admission_token_ttl: Duration,
+    /// Clave con la que el personal de soporte busca las órdenes de un cliente;
+    /// sin ella esas búsquedas están desactivadas.
+    pub staff_key: Option<String>,
+}
+
+impl ServerConfig {
+    /// Lee la configuración del entorno, usando valores por defecto para lo que no esté definido.
+    ///
+    /// - `TICKETBUDDY_HOLD_TTL_SECS`: segundos que dura una reserva temporal (hasta 3600).
+    /// - `TICKETBUDDY_DB_PATH`: archivo de la base de datos SQLite.
+    /// - `TICKETBUDDY_ORPHAN_RULE`: `off`, `singles` o `pairs`.
+    /// - `TICKETBUDDY_DISTANCING_SEATS`: asientos vacíos a cada lado de un grupo (hasta 10).
+    /// - `TICKETBUDDY_DISTANCING_ROWS`: filas vacías delante y detrás de un grupo (hasta 10).
+    /// - `TICKETBUDDY_ADMISSIONS_PER_MINUTE`: ritmo de la sala de espera (0 la desactiva).
+    /// - `TICKETBUDDY_ADMISSION_TOKEN_TTL_SECS`: segundos que vale un token de admisión
+    ///   (hasta 86400).
+    /// - `TICKETBUDDY_STAFF_KEY`: clave del personal para buscar órdenes por cliente.
+    pub fn from_env() -> Result<ServerConfig, String> {
+        let hold_ttl_secs = read_u64("TICKETBUDDY_HOLD_TTL_SECS", DEFAULT_HOLD_TTL_SECS)?;
+        if hold_ttl_secs == 0 {
+            return Err("TICKETBUDDY_HOLD_TTL_SECS debe ser mayor que cero".to_string());
+        }
+        if hold_ttl_secs > MAX_HOLD_TTL_SECS {
+            return Err(format!(
+                "TICKETBUDDY_HOLD_TTL_SECS no puede ser mayor que {}, se recibió {}",
+                MAX_HOLD_TTL_SECS, hold_ttl_secs
+            ));
+        }
+
+        let db_path = env::var("TICKETBUDDY_DB_PATH")
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| PathBuf::from(DEFAULT_DB_PATH));
+
+        let orphan_rule = match env::var("TICKETBUDDY_ORPHAN_RULE") {
+            Ok(value) => OrphanRule::parse(value.trim()).ok_or_else(|| {
+                format!(
+                    "TICKETBUDDY_ORPHAN_RULE debe ser off, singles o pairs, se recibió {:?}",
+                    value
+                )
+            })?,
+            Err(_) => DEFAULT_ORPHAN_RULE,
+        };
+
+        let distancing = Distancing {
+            seats: read_distancing("TICKETBUDDY_DISTANCING_SEATS")?,
+            rows: read_distancing("TICKETBUDDY_DISTANCING_ROWS")?,
+        };
+
+        let admissions_per_minute = read_u64("TICKETBUDDY_ADMISSIONS_PER_MINUTE", 0)?;
+        let admission_token_ttl_secs = read_u64(
+            "TICKETBUDDY_ADMISSION_TOKEN_TTL_SECS",
+            DEFAULT_ADMISSION_TOKEN_TTL_SECS,
+        )?;
+        if admission_token_ttl_secs == 0 {
+            return Err("TICKETBUDDY_ADMISSION_TOKEN_TTL_SECS debe ser mayor que cero".to_string());
+        }
+        if admission_token_ttl_secs > MAX_ADMISSION_TOKEN_TTL_SECS {
+            return Err(format!(
+                "TICKETBUDDY_ADMISSION_TOKEN_TTL_SECS no puede ser mayor que {}, se recibió {}",
+                MAX_ADMISSION_TOKEN_TTL_SECS, admission_token_ttl_secs
+            ));
+        }
+
+        let staff_key = match env::var("TICKETBUDDY_STAFF_KEY") {
+            Ok(key) if key.trim().is_empty() => {
+                return Err("TICKETBUDDY_STAFF_KEY no puede estar vacía".to_string());
+            }
+            Ok(key) => Some(key.trim().to_string()),
+            Err(_) => None,
+        };
+
+        Ok(ServerConfig {
+            hold_ttl: Duration::from_secs(hold_ttl_secs),
+            db_path,
+            orphan_rule,
+            distancing,
+            admissions_per_minute,
+            admission_token_ttl: Duration::from_secs(admission_token_ttl_secs),
+            staff_key,
+        })
+    }
+}
+
+/// Función auxiliar para leer un entero de una variable de entorno.
+fn read_u64(name: &str, default: u64) -> Result<u64, String> {
+    match env::var(name) {
+        Ok(value) => value
+            .trim()
+            .parse()
+            .map_err(|_| format!("{} debe ser un número entero, se recibió {:?}", name, value)),
+        Err(_) => Ok(default),
+    }
+}
+
+/// Función auxiliar para leer una separación del modo de distanciamiento (0 si no se indica).
+fn read_distancing(name: &str) -> Result<u32, String> {
+    let value = read_u64(name, 0)?;
+    if value > MAX_DISTANCING as u64 {
+        return Err(format!(
+            "{} no puede ser mayor que {}, se recibió {}",
+            name, MAX_DISTANCING, value
+        ));
+    }
+    Ok(value as u32)
+}
@@ -0,0 +1,686 @@
+// events.rs
+
+use crate::holds::HoldRegistry;
+use crate::orders::unix_now;
+use crate::seat_manager::{SeatKey, SeatMap, SeatStatus};
+use crate::venue::{load_venue, CategoryId, Section, SectionId, Venue, VenueError};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+use toml::value::{Datetime, Offset};
+
+/// Identificador de un evento (por ejemplo, "2026-11-06").
+pub type EventId = String;
+
+/// Cada cuánto se revisa si un evento cambió de estado.
+const LIFECYCLE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Cantidad de cambios de estado que se conservan para las sesiones más lentas.
+const STATUS_CAPACITY: usize = 16;
+
+/// Motivo guardado para los asientos bloqueados que no indican otro.
+const DEFAULT_BLOCKED_REASON: &str = "vendido fuera del sistema";
+
+/// Definición de los eventos a la venta, leída desde un archivo TOML.
+#[derive(Debug, Deserialize)]
+pub struct EventsConfig {
+    #[serde(rename = "event")]
+    pub events: Vec<EventConfig>,
+}
+
+/// Definición de un evento tal como aparece en el archivo.
+#[derive(Debug, Deserialize)]
+pub struct EventConfig {
+    pub id: EventId,
+    pub name: String,
+    /// Fecha y hora de inicio, con su zona horaria.
+    pub starts_at: Datetime,
+    /// Archivo con la disposición del recinto, relativo al archivo de eventos.
+    pub venue: PathBuf,
+    /// Inicio de la preventa; sin él no hay preventa.
+    #[serde(default)]
+    pub presale_at: Option<Datetime>,
+    /// Inicio de la venta general; sin él el evento sigue en preparación.
+    #[serde(default)]
+    pub on_sale_at: Option<Datetime>,
+    /// Cierre de la venta; por defecto, el inicio del evento.
+    #[serde(default)]
+    pub closes_at: Option<Datetime>,
+    /// Detiene la venta sin importar el calendario.
+    #[serde(default)]
+    pub paused: bool,
+    /// Códigos que dan acceso a la preventa.
+    #[serde(default, rename = "presale_code")]
+    pub presale_codes: Vec<PresaleCodeConfig>,
+    /// Asientos que no se venden en este evento (por ejemplo, vendidos fuera del sistema).
+    #[serde(default)]
+    pub blocked: Vec<BlockedSeatsConfig>,
+}
+
+/// Asientos de una fila bloqueados desde el inicio, tal como aparecen en el archivo.
+#[derive(Debug, Deserialize)]
+pub struct BlockedSeatsConfig {
+    pub section: SectionId,
+    pub row: u32,
+    pub numbers: Vec<u32>,
+    /// Motivo del bloqueo; por defecto, "vendido fuera del sistema".
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+/// Código de preventa tal como aparece en el archivo.
+#[derive(Debug, Deserialize)]
+pub struct PresaleCodeConfig {
+    pub code: String,
+    /// Categorías completas que habilita el código.
+    #[serde(default)]
+    pub categories: Vec<CategoryId>,
+    /// Secciones sueltas que habilita el código.
+    #[serde(default)]
+    pub sections: Vec<SectionId>,
+    /// Período en que vale el código, dentro de la preventa; por defecto, toda la preventa.
+    #[serde(default)]
+    pub valid_from: Option<Datetime>,
+    #[serde(default)]
+    pub valid_until: Option<Datetime>,
+}
+
+/// Código de preventa que habilita algunas categorías o secciones durante un período.
+#[derive(Debug, Clone)]
+pub struct PresaleCode {
+    pub code: String,
+    pub categories: Vec<CategoryId>,
+    pub sections: Vec<SectionId>,
+    /// Segundos desde la época Unix en que empieza y termina a valer el código.
+    pub valid_from: Option<u64>,
+    pub valid_until: Option<u64>,
+}
+
+impl PresaleCode {
+    /// Indica si el texto recibido es este código (sin distinguir mayúsculas).
+    fn matches(&self, code: &str) -> bool {
+        self.code.eq_ignore_ascii_case(code.trim())
+    }
+
+    /// Indica si el código vale en el momento dado.
+    fn is_valid_at(&self, now: u64) -> bool {
+        self.valid_from.is_none_or(|from| now >= from)
+            && self.valid_until.is_none_or(|until| now < until)
+    }
+
+    /// Indica si el código habilita los asientos de la sección.
+    pub fn unlocks(&self, section: &Section) -> bool {
+        self.categories.contains(&section.category) || self.sections.contains(&section.id)
+    }
+}
+
+/// Estado de venta de un evento.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventStatus {
+    /// En preparación: todavía no tiene fecha de venta o no llegó a la preventa.
+    Draft,
+    /// Venta anticipada, antes de la venta general.
+    Presale,
+    /// Venta general.
+    OnSale,
+    /// Venta detenida por los organizadores.
+    Paused,
+    /// No quedan asientos libres ni reservados.
+    SoldOut,
+    /// La venta terminó.
+    Closed,
+}
+
+impl EventStatus {
+    /// Indica si el calendario tiene la venta abierta, anticipada o general.
+    pub fn is_open(self) -> bool {
+        matches!(self, EventStatus::Presale | EventStatus::OnSale)
+    }
+}
+
+impl fmt::Display for EventStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            EventStatus::Draft => "en preparación",
+            EventStatus::Presale => "en preventa",
+            EventStatus::OnSale => "a la venta",
+            EventStatus::Paused => "con la venta pausada",
+            EventStatus::SoldOut => "agotado",
+            EventStatus::Closed => "con la venta cerrada",
+        };
+        f.write_str(text)
+    }
+}
+
+/// Calendario de venta de un evento, en segundos desde la época Unix.
+#[derive(Debug, Clone, Copy)]
+pub struct SalesSchedule {
+    pub presale_at: Option<u64>,
+    pub on_sale_at: Option<u64>,
+    pub closes_at: u64,
+    pub paused: bool,
+}
+
+impl SalesSchedule {
+    /// Estado que indica el calendario en el momento dado, sin mirar los asientos.
+    pub fn status_at(&self, now: u64) -> EventStatus {
+        let Some(on_sale_at) = self.on_sale_at else {
+            return EventStatus::Draft;
+        };
+        if now >= self.closes_at {
+            EventStatus::Closed
+        } else if self.paused {
+            EventStatus::Paused
+        } else if now >= on_sale_at {
+            EventStatus::OnSale
+        } else if self.presale_at.is_some_and(|presale_at| now >= presale_at) {
+            EventStatus::Presale
+        } else {
+            EventStatus::Draft
+        }
+    }
+}
+
+/// Evento ya validado, con la disposición de su recinto pero todavía sin inventario.
+#[derive(Debug)]
+pub struct EventDefinition {
+    pub id: EventId,
+    pub name: String,
+    /// Segundos desde la época Unix en que empieza el evento.
+    pub starts_at: u64,
+    pub venue: Arc<Venue>,
+    pub schedule: SalesSchedule,
+    pub presale_codes: Vec<PresaleCode>,
+    /// Asientos que se bloquean al crear el inventario del evento, con su motivo.
+    pub blocked: Vec<(SeatKey, String)>,
+}
+
+/// Evento a la venta: una función en un recinto, con su propio inventario de asientos.
+pub struct Event {
+    pub id: EventId,
+    pub name: String,
+    /// Segundos desde la época Unix en que empieza el evento.
+    pub starts_at: u64,
+    pub venue: Arc<Venue>,
+    pub schedule: SalesSchedule,
+    presale_codes: Vec<PresaleCode>,
+    pub seats: SeatMap,
+    pub holds: Arc<HoldRegistry>,
+    statuses: broadcast::Sender<EventStatus>,
+}
+
+impl Event {
+    /// Crea el evento a partir de su definición y de su inventario ya cargado.
+    pub fn new(definition: EventDefinition, seats: SeatMap, holds: Arc<HoldRegistry>) -> Self {
+        let (statuses, _) = broadcast::channel(STATUS_CAPACITY);
+        Event {
+            id: definition.id,
+            name: definition.name,
+            starts_at: definition.starts_at,
+            venue: definition.venue,
+            schedule: definition.schedule,
+            presale_codes: definition.presale_codes,
+            seats,
+            holds,
+            statuses,
+        }
+    }
+
+    /// Estado de venta actual, según el calendario y los asientos que quedan.
+    pub fn status(&self) -> EventStatus {
+        let status = self.schedule.status_at(unix_now());
+        if status.is_open() && self.is_sold_out() {
+            EventStatus::SoldOut
+        } else {
+            status
+        }
+    }
+
+    /// Indica si todos los asientos están vendidos, bloqueados o como separación.
+    /// Los asientos reservados temporalmente pueden volver a quedar libres.
+    fn is_sold_out(&self) -> bool {
+        let seats_guard = self.seats.lock().unwrap();
+        seats_guard
+            .values()
+            .all(|seat| !seat.status.is_free() && !matches!(seat.status, SeatStatus::Held { .. }))
+    }
+
+    /// Busca un código de preventa del evento que valga en este momento.
+    pub fn presale_code(&self, code: &str) -> Option<&PresaleCode> {
+        let now = unix_now();
+        self.presale_codes
+            .iter()
+            .find(|presale| presale.matches(code) && presale.is_valid_at(now))
+    }
+
+    /// Crea un receptor que recibirá cada cambio de estado de venta del evento.
+    pub fn subscribe(&self) -> broadcast::Receiver<EventStatus> {
+        self.statuses.subscribe()
+    }
+}
+
+/// Eventos a la venta, ordenados por fecha de inicio.
+pub struct EventCatalog {
+    events: Vec<Arc<Event>>,
+}
+
+impl EventCatalog {
+    /// Crea el catálogo; debe recibir al menos un evento.
+    pub fn new(mut events: Vec<Event>) -> Self {
+        assert!(
+            !events.is_empty(),
+            "el catálogo necesita al menos un evento"
+        );
+        events.sort_by(|a, b| a.starts_at.cmp(&b.starts_at).then_with(|| a.id.cmp(&b.id)));
+        EventCatalog {
+            events: events.into_iter().map(Arc::new).collect(),
+        }
+    }
+
+    /// Busca un evento por su identificador.
+    pub fn get(&self, id: &str) -> Option<&Arc<Event>> {
+        self.events.iter().find(|event| event.id == id)
+    }
+
+    /// Todos los eventos, del más próximo al más lejano.
+    pub fn all(&self) -> &[Arc<Event>] {
+        &self.events
+    }
+
+    /// Evento que se muestra a los clientes al conectarse: el más próximo.
+    pub fn first(&self) -> &Arc<Event> {
+        &self.events[0]
+    }
+}
+
+/// Errores posibles al cargar la definición de los eventos.
+#[derive(Debug)]
+pub enum EventError {
+    Io(std::io::Error),
+    Parse(toml::de::Error),
+    /// El recinto de un evento no pudo cargarse.
+    Venue(PathBuf, VenueError),
+    Invalid(String),
+}
+
+impl fmt::Display for EventError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EventError::Io(e) => write!(f, "no se pudo leer el archivo de eventos: {}", e),
+            EventError::Parse(e) => write!(f, "formato de eventos inválido: {}", e),
+            EventError::Venue(path, e) => write!(f, "{}: {}", path.display(), e),
+            EventError::Invalid(msg) => write!(f, "definición de eventos inválida: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for EventError {}
+
+impl From<std::io::Error> for EventError {
+    fn from(e: std::io::Error) -> Self {
+        EventError::Io(e)
+    }
+}
+
+impl From<toml::de::Error> for EventError {
+    fn from(e: toml::de::Error) -> Self {
+        EventError::Parse(e)
+    }
+}
+
+/// Función para cargar y validar los eventos desde un archivo.
+/// Los eventos que comparten archivo de recinto comparten también su disposición.
+pub fn load_events(path: &Path) -> Result<Vec<EventDefinition>, EventError> {
+    let contents = fs::read_to_string(path)?;
+    let config: EventsConfig = toml::from_str(&contents)?;
+    if config.events.is_empty() {
+        return Err(EventError::Invalid(
+            "el archivo no define eventos".to_string(),
+        ));
+    }
+
+    let base = path.parent().unwrap_or_else(|| Path::new(""));
+    let mut venues: HashMap<PathBuf, Arc<Venue>> = HashMap::new();
+    let mut ids = HashSet::new();
+    let mut events = Vec::with_capacity(config.events.len());
+    for event in config.events {
+        if event.id.trim().is_empty() {
+            return Err(EventError::Invalid(
+                "hay un evento sin identificador".to_string(),
+            ));
+        }
+        if !ids.insert(event.id.clone()) {
+            return Err(EventError::Invalid(format!(
+                "el evento {} está definido más de una vez",
+                event.id
+            )));
+        }
+        let starts_at = unix_seconds(&event.starts_at).ok_or_else(|| {
+            EventError::Invalid(format!(
+                "el evento {} debe indicar fecha, hora y zona horaria de inicio",
+                event.id
+            ))
+        })?;
+
+        let schedule = sales_schedule(&event, starts_at)?;
+
+        let venue_path = base.join(&event.venue);
+        let venue = match venues.get(&venue_path) {
+            Some(venue) => Arc::clone(venue),
+            None => {
+                let venue = load_venue(&venue_path)
+                    .map_err(|e| EventError::Venue(venue_path.clone(), e))?;
+                let venue = Arc::new(venue);
+                venues.insert(venue_path, Arc::clone(&venue));
+                venue
+            }
+        };
+        let presale_codes = presale_codes(&event, &venue)?;
+        let blocked = blocked_seats(&event, &venue)?;
+
+        events.push(EventDefinition {
+            id: event.id,
+            name: event.name,
+            starts_at,
+            venue,
+            schedule,
+            presale_codes,
+            blocked,
+        });
+    }
+    Ok(events)
+}
+
+/// Función auxiliar para validar el calendario de venta de un evento.
+fn sales_schedule(event: &EventConfig, starts_at: u64) -> Result<SalesSchedule, EventError> {
+    let moment = |field: &str, value: &Option<Datetime>| match value {
+        Some(datetime) => unix_seconds(datetime).map(Some).ok_or_else(|| {
+            EventError::Invalid(format!(
+                "el evento {} debe indicar fecha, hora y zona horaria en {}",
+                event.id, field
+            ))
+        }),
+        None => Ok(None),
+    };
+    let schedule = SalesSchedule {
+        presale_at: moment("presale_at", &event.presale_at)?,
+        on_sale_at: moment("on_sale_at", &event.on_sale_at)?,
+        closes_at: moment("closes_at", &event.closes_at)?.unwrap_or(starts_at),
+        paused: event.paused,
+    };
+
+    let invalid = |msg: &str| {
+        Err(EventError::Invalid(format!(
+            "el evento {} {}",
+            event.id, msg
+        )))
+    };
+    match (schedule.presale_at, schedule.on_sale_at) {
+        (Some(_), None) => invalid("tiene preventa pero no venta general"),
+        (Some(presale_at), Some(on_sale_at)) if presale_at >= on_sale_at => {
+            invalid("debe empezar la preventa antes de la venta general")
+        }
+        (_, Some(on_sale_at)) if on_sale_at >= schedule.closes_at => {
+            invalid("debe abrir la venta antes de cerrarla")
+        }
+        _ => Ok(schedule),
+    }
+}
+
+/// Función auxiliar para validar los códigos de preventa de un evento contra su recinto.
+fn presale_codes(event: &EventConfig, venue: &Venue) -> Result<Vec<PresaleCode>, EventError> {
+    let invalid = |msg: String| EventError::Invalid(format!("el evento {} {}", event.id, msg));
+    let moment = |code: &str, value: &Option<Datetime>| match value {
+        Some(datetime) => unix_seconds(datetime)
+            .map(Some)
+            .ok_or_else(|| invalid(format!("debe indicar la zona horaria del código {}", code))),
+        None => Ok(None),
+    };
+
+    let mut codes: Vec<PresaleCode> = Vec::with_capacity(event.presale_codes.len());
+    for config in &event.presale_codes {
+        let code = config.code.trim();
+        if code.is_empty() {
+            return Err(invalid("tiene un código de preventa vacío".to_string()));
+        }
+        if codes.iter().any(|presale| presale.matches(code)) {
+            return Err(invalid(format!("repite el código de preventa {}", code)));
+        }
+        if event.presale_at.is_none() {
+            return Err(invalid(format!(
+                "tiene el código {} pero no tiene preventa",
+                code
+            )));
+        }
+        if config.categories.is_empty() && config.sections.is_empty() {
+            return Err(invalid(format!(
+                "no indica qué habilita el código {}",
+                code
+            )));
+        }
+        if let Some(category) = config
+            .categories
+            .iter()
+            .find(|c| venue.category(c).is_none())
+        {
+            return Err(invalid(format!(
+                "habilita en el código {} la categoría desconocida {}",
+                code, category
+            )));
+        }
+        if let Some(section) = config.sections.iter().find(|s| venue.section(s).is_none()) {
+            return Err(invalid(format!(
+                "habilita en el código {} la sección desconocida {}",
+                code, section
+            )));
+        }
+
+        let valid_from = moment(code, &config.valid_from)?;
+        let valid_until = moment(code, &config.valid_until)?;
+        if let (Some(from), Some(until)) = (valid_from, valid_until) {
+            if from >= until {
+                return Err(invalid(format!(
+                    "debe empezar el código {} antes de terminarlo",
+                    code
+                )));
+            }
+        }
+
+        codes.push(PresaleCode {
+            code: code.to_string(),
+            categories: config.categories.clone(),
+            sections: config.sections.clone(),
+            valid_from,
+            valid_until,
+        });
+    }
+    Ok(codes)
+}
+
+/// Función auxiliar para validar los asientos bloqueados de un evento contra su recinto.
+fn blocked_seats(event: &EventConfig, venue: &Venue) -> Result<Vec<(SeatKey, String)>, EventError> {
+    let invalid = |msg: String| EventError::Invalid(format!("el evento {} {}", event.id, msg));
+
+    let mut keys = HashSet::new();
+    let mut blocked = Vec::new();
+    for group in &event.blocked {
+        let Some(section) = venue.section(&group.section) else {
+            return Err(invalid(format!(
+                "bloquea asientos de la sección desconocida {}",
+                group.section
+            )));
+        };
+        let seat_count = match group.row.checked_sub(1) {
+            Some(index) => section.rows.get(index as usize).copied(),
+            None => None,
+        };
+        let Some(seat_count) = seat_count else {
+            return Err(invalid(format!(
+                "bloquea asientos de la fila {} que la sección {} no tiene",
+                group.row, section.id
+            )));
+        };
+        if group.numbers.is_empty() {
+            return Err(invalid(format!(
+                "no indica qué asientos bloquea en la fila {} de la sección {}",
+                group.row, section.id
+            )));
+        }
+        let reason = match &group.reason {
+            Some(reason) if reason.trim().is_empty() => {
+                return Err(invalid(format!(
+                    "deja vacío el motivo del bloqueo en la fila {} de la sección {}",
+                    group.row, section.id
+                )));
+            }
+            Some(reason) => reason.trim().to_string(),
+            None => DEFAULT_BLOCKED_REASON.to_string(),
+        };
+
+        for &number in &group.numbers {
+            if number == 0 || number > seat_count {
+                return Err(invalid(format!(
+                    "bloquea el asiento {} que la fila {} de la sección {} no tiene",
+                    number, group.row, section.id
+                )));
+            }
+            let key: SeatKey = (section.id.clone(), group.row, number);
+            if !keys.insert(key.clone()) {
+                return Err(invalid(format!(
+                    "bloquea más de una vez el asiento {}-{}-{}",
+                    section.id, group.row, number
+                )));
+            }
+            blocked.push((key, reason.clone()));
+        }
+    }
+    Ok(blocked)
+}
+
+/// Inicia la tarea que sigue el estado de venta del evento y avisa cada cambio.
+pub fn spawn_lifecycle(event: Arc<Event>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(LIFECYCLE_INTERVAL);
+        let mut current = event.status();
+        println!("El evento {} está {}", event.id, current);
+        loop {
+            interval.tick().await;
+            let status = event.status();
+            if status != current {
+                println!("El evento {} pasó a estar {}", event.id, status);
+                current = status;
+                let _ = event.statuses.send(status);
+            }
+        }
+    })
+}
+
+/// Función auxiliar para convertir una fecha y hora TOML con zona horaria en segundos
+/// desde la época Unix. Retorna `None` si falta alguna parte o es anterior a 1970.
+fn unix_seconds(datetime: &Datetime) -> Option<u64> {
+    let (date, time, offset) = (datetime.date?, datetime.time?, datetime.offset?);
+    let offset_minutes = match offset {
+        Offset::Z => 0,
+        Offset::Custom { minutes } => minutes as i64,
+    };
+    let days = days_from_civil(date.year as i64, date.month as i64, date.day as i64);
+    let seconds =
+        days * 86_400 + time.hour as i64 * 3_600 + time.minute as i64 * 60 + time.second as i64
+            - offset_minutes * 60;
+    u64::try_from(seconds).ok()
+}
+
+/// Función auxiliar para contar los días desde el 1 de enero de 1970 hasta una fecha
+/// del calendario gregoriano.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schedule(presale_at: Option<u64>, on_sale_at: Option<u64>, paused: bool) -> SalesSchedule {
+        SalesSchedule {
+            presale_at,
+            on_sale_at,
+            closes_at: 300,
+            paused,
+        }
+    }
+
+    fn datetime(text: &str) -> Datetime {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn status_follows_the_schedule_boundaries() {
+        let schedule = schedule(Some(100), Some(200), false);
+        assert_eq!(schedule.status_at(99), EventStatus::Draft);
+        assert_eq!(schedule.status_at(100), EventStatus::Presale);
+        assert_eq!(schedule.status_at(199), EventStatus::Presale);
+        assert_eq!(schedule.status_at(200), EventStatus::OnSale);
+        assert_eq!(schedule.status_at(299), EventStatus::OnSale);
+        assert_eq!(schedule.status_at(300), EventStatus::Closed);
+    }
+
+    #[test]
+    fn status_without_presale_goes_from_draft_to_on_sale() {
+        let schedule = schedule(None, Some(200), false);
+        assert_eq!(schedule.status_at(199), EventStatus::Draft);
+        assert_eq!(schedule.status_at(200), EventStatus::OnSale);
+    }
+
+    #[test]
+    fn status_without_on_sale_is_always_draft() {
+        let schedule = schedule(None, None, true);
+        assert_eq!(schedule.status_at(0), EventStatus::Draft);
+        assert_eq!(schedule.status_at(1_000), EventStatus::Draft);
+    }
+
+    #[test]
+    fn paused_holds_until_the_sale_closes() {
+        let schedule = schedule(Some(100), Some(200), true);
+        assert_eq!(schedule.status_at(0), EventStatus::Paused);
+        assert_eq!(schedule.status_at(250), EventStatus::Paused);
+        assert_eq!(schedule.status_at(300), EventStatus::Closed);
+    }
+
+    #[test]
+    fn unix_seconds_applies_the_offset() {
+        let expected = Some(1_794_016_800);
+        assert_eq!(unix_seconds(&datetime("2026-11-07T02:00:00Z")), expected);
+        assert_eq!(unix_seconds(&datetime("2026-11-06T20:00:00-06:00")), expected);
+        assert_eq!(unix_seconds(&datetime("2026-11-07T07:30:00+05:30")), expected);
+    }
+
+    #[test]
+    fn unix_seconds_needs_date_time_and_offset() {
+        assert_eq!(unix_seconds(&datetime("2026-11-06T20:00:00")), None);
+        assert_eq!(unix_seconds(&datetime("2026-11-06")), None);
+        assert_eq!(unix_seconds(&datetime("20:00:00")), None);
+        assert_eq!(unix_seconds(&datetime("1969-12-31T23:59:59Z")), None);
+        assert_eq!(unix_seconds(&datetime("1970-01-01T00:00:00Z")), Some(0));
+    }
+
+    #[test]
+    fn days_from_civil_counts_leap_days() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(2000, 3, 1), 11_017);
+        assert_eq!(days_from_civil(2024, 2, 29), 19_782);
+        assert_eq!(days_from_civil(2024, 3, 1), 19_783);
+        assert_eq!(days_from_civil(1969, 12, 31), -1);
+    }
+}
@@ -0,0 +1,360 @@
+// holds.rs
+
+use crate::events::EventId;
+use crate::orders::Order;
+use crate::seat_manager::{
+    transition_seats, transition_seats_apart, Distancing, SeatError, SeatKey, SeatMap, SeatStatus,
+};
+use crate::storage::Storage;
+use crate::venue::Venue;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+
+/// Identificador de una sesión WebSocket.
+pub type SessionId = u64;
+
+/// Cada cuánto revisa el recolector las reservas vencidas.
+const REAPER_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Cantidad de avisos de vencimiento que se conservan para las sesiones más lentas.
+const EXPIRATIONS_CAPACITY: usize = 256;
+
+/// Intentos para dejar la separación de una compra si otro cliente toma alguno de sus asientos.
+const BUFFER_ATTEMPTS: usize = 3;
+
+/// Aviso de que las reservas temporales de una sesión vencieron.
+#[derive(Debug, Clone)]
+pub struct HoldExpired {
+    pub session: SessionId,
+    pub seats: Vec<SeatKey>,
+}
+
+/// Administra las reservas temporales (`SeatStatus::Held`) de un evento y su vencimiento.
+pub struct HoldRegistry {
+    event: EventId,
+    ttl: Duration,
+    seats: SeatMap,
+    storage: Arc<Storage>,
+    expirations: broadcast::Sender<HoldExpired>,
+    /// Separación que se deja alrededor de cada compra.
+    distancing: Distancing,
+}
+
+impl HoldRegistry {
+    pub fn new(
+        event: EventId,
+        ttl: Duration,
+        seats: SeatMap,
+        storage: Arc<Storage>,
+        distancing: Distancing,
+    ) -> Self {
+        let (expirations, _) = broadcast::channel(EXPIRATIONS_CAPACITY);
+        HoldRegistry {
+            event,
+            ttl,
+            seats,
+            storage,
+            expirations,
+            distancing,
+        }
+    }
+
+    /// Duración de cada reserva temporal.
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Estado de un asiento reservado por la sesión desde ahora hasta que venza.
+    fn held_by(&self, session: SessionId) -> (SeatStatus, SystemTime) {
+        let status = SeatStatus::Held {
+            session,
+            expires_at: Instant::now() + self.ttl,
+        };
+        (status, SystemTime::now() + self.ttl)
+    }
+
+    /// Reserva temporalmente todos los asientos indicados para la sesión.
+    /// Si alguno no está libre, o con el modo de distanciamiento queda junto a otro grupo
+    /// reservado o vendido, no se reserva ninguno y se retorna el conflicto.
+    pub fn hold_seats(&self, session: SessionId, seats: &[SeatKey]) -> Result<(), SeatError> {
+        let (held, stored_expires_at) = self.held_by(session);
+        transition_seats_apart(
+            &self.seats,
+            seats,
+            SeatStatus::is_free,
+            self.distancing,
+            &held,
+            || {
+                self.storage
+                    .hold_seats(&self.event, session, seats, stored_expires_at)
+            },
+        )
+    }
+
+    /// Renueva la reserva de los asientos que la sesión ya tiene reservados.
+    pub fn renew(&self, session: SessionId, seats: &[SeatKey]) {
+        let owned: Vec<SeatKey> = {
+            let seats_guard = self.seats.lock().unwrap();
+            seats
+                .iter()
+                .filter(|seat| {
+                    seats_guard
+                        .get(*seat)
+                        .is_some_and(|s| s.status.is_held_by(session))
+                })
+                .cloned()
+                .collect()
+        };
+        let (held, stored_expires_at) = self.held_by(session);
+        let renewed = transition_seats(
+            &self.seats,
+            &owned,
+            |status| status.is_held_by(session),
+            &held,
+            || {
+                self.storage
+                    .hold_seats(&self.event, session, &owned, stored_expires_at)
+            },
+        );
+        if let Err(e) = renewed {
+            println!(
+                "No se pudo renovar la reserva de la sesión {}: {}",
+                session, e
+            );
+        }
+    }
+
+    /// Libera los asientos indicados que sigan reservados por la sesión.
+    pub fn release(&self, session: SessionId, seats: &[SeatKey]) {
+        for seat in seats {
+            let _ = self.free_seat(seat, |status| status.is_held_by(session));
+        }
+    }
+
+    /// Indica si todos los asientos siguen reservados por la sesión.
+    pub fn holds_all(&self, session: SessionId, seats: &[SeatKey]) -> bool {
+        let seats_guard = self.seats.lock().unwrap();
+        seats.iter().all(|seat| {
+            seats_guard
+                .get(seat)
+                .is_some_and(|s| s.status.is_held_by(session))
+        })
+    }
+
+    /// Vende los asientos reservados por la sesión y guarda la orden que los compró.
+    /// Si alguno ya no está reservado por ella no se cambia ninguno.
+    /// Con el modo de distanciamiento, los asientos libres alrededor quedan como separación.
+    /// Los topes de compra de las categorías de `venue` se verifican al guardar la orden.
+    pub fn book(
+        &self,
+        session: SessionId,
+        seats: &[SeatKey],
+        order: &Order,
+        venue: &Venue,
+    ) -> Result<(), SeatError> {
+        let booked = SeatStatus::Booked {
+            order: order.id.clone(),
+        };
+        transition_seats(
+            &self.seats,
+            seats,
+            |status| status.is_held_by(session),
+            &booked,
+            || self.storage.book_seats(session, order, venue),
+        )?;
+        self.add_buffer(seats, order);
+        Ok(())
+    }
+
+    /// Deja como separación los asientos libres alrededor de los asientos vendidos.
+    /// Si otro cliente toma alguno mientras tanto, se vuelve a calcular la separación.
+    fn add_buffer(&self, seats: &[SeatKey], order: &Order) {
+        let buffer = SeatStatus::Buffer {
+            order: order.id.clone(),
+        };
+        for _ in 0..BUFFER_ATTEMPTS {
+            let keys = {
+                let seats_guard = self.seats.lock().unwrap();
+                self.distancing.buffer_around(&seats_guard, seats)
+            };
+            if keys.is_empty() {
+                return;
+            }
+            let result = transition_seats(&self.seats, &keys, SeatStatus::is_free, &buffer, || {
+                self.storage.buffer_seats(order, &keys)
+            });
+            match result {
+                Ok(()) => return,
+                Err(SeatError::Unavailable(_)) => continue,
+                Err(e) => {
+                    eprintln!(
+                        "No se pudo dejar la separación de la orden {}: {}",
+                        order.id, e
+                    );
+                    return;
+                }
+            }
+        }
+        eprintln!(
+            "No se pudo dejar la separación de la orden {}: los asientos cambiaron",
+            order.id
+        );
+    }
+
+    /// Crea un receptor que recibirá los avisos de reservas vencidas.
+    pub fn subscribe(&self) -> broadcast::Receiver<HoldExpired> {
+        self.expirations.subscribe()
+    }
+
+    /// Libera los asientos cuya reserva venció y avisa a las sesiones dueñas.
+    pub fn expire(&self, now: Instant) -> Vec<HoldExpired> {
+        let expired: Vec<(SeatKey, SessionId)> = {
+            let seats_guard = self.seats.lock().unwrap();
+            seats_guard
+                .iter()
+                .filter_map(|(seat, s)| match s.status {
+                    SeatStatus::Held {
+                        session,
+                        expires_at,
+                    } if expires_at <= now => Some((seat.clone(), session)),
+                    _ => None,
+                })
+                .collect()
+        };
+
+        let mut by_session: HashMap<SessionId, Vec<SeatKey>> = HashMap::new();
+        for (seat, session) in expired {
+            // Solo se libera si sigue vencida (la sesión pudo renovarla o pagarla mientras tanto)
+            let still_expired = |status: &SeatStatus| {
+                matches!(status, SeatStatus::Held { session: owner, expires_at }
+                    if *owner == session && *expires_at <= now)
+            };
+            if self.free_seat(&seat, still_expired).is_ok() {
+                by_session.entry(session).or_default().push(seat);
+            }
+        }
+
+        let notices: Vec<HoldExpired> = by_session
+            .into_iter()
+            .map(|(session, seats)| HoldExpired { session, seats })
+            .collect();
+        for notice in &notices {
+            let _ = self.expirations.send(notice.clone());
+        }
+        notices
+    }
+
+    /// Libera un asiento reservado temporalmente y guarda el cambio.
+    /// Si no pudo guardarse, el asiento sigue reservado y el recolector lo reintenta al vencer.
+    fn free_seat<P>(&self, seat: &SeatKey, expected: P) -> Result<(), SeatError>
+    where
+        P: Fn(&SeatStatus) -> bool,
+    {
+        let keys = std::slice::from_ref(seat);
+        let result = transition_seats(&self.seats, keys, expected, &SeatStatus::Free, || {
+            self.storage.release_seats(&self.event, keys)
+        });
+        if let Err(SeatError::Storage(e)) = &result {
+            eprintln!("No se pudo liberar el asiento: {}", e);
+        }
+        result
+    }
+}
+
+/// Inicia la tarea que libera periódicamente las reservas vencidas.
+pub fn spawn_reaper(holds: Arc<HoldRegistry>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(REAPER_INTERVAL.min(holds.ttl()));
+        loop {
+            interval.tick().await;
+            for notice in holds.expire(Instant::now()) {
+                println!(
+                    "Reserva vencida para la sesión {} en el evento {}: {} asientos liberados",
+                    notice.session,
+                    holds.event,
+                    notice.seats.len()
+                );
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::seat_manager::row_of_seats;
+
+    const TTL: Duration = Duration::from_secs(60);
+
+    fn registry() -> HoldRegistry {
+        HoldRegistry::new(
+            "2026-11-06".to_string(),
+            TTL,
+            row_of_seats(&[SeatStatus::Free, SeatStatus::Free]),
+            Arc::new(Storage::open_in_memory()),
+            Distancing::default(),
+        )
+    }
+
+    fn seat(number: u32) -> SeatKey {
+        ("F".to_string(), 1, number)
+    }
+
+    fn status(holds: &HoldRegistry, number: u32) -> SeatStatus {
+        holds.seats.lock().unwrap()[&seat(number)].status.clone()
+    }
+
+    fn expires_at(holds: &HoldRegistry, number: u32) -> Instant {
+        match status(holds, number) {
+            SeatStatus::Held { expires_at, .. } => expires_at,
+            other => panic!("el asiento no está reservado: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn expired_hold_is_freed_and_announced() {
+        let holds = registry();
+        let mut expirations = holds.subscribe();
+        holds.hold_seats(1, &[seat(1)]).unwrap();
+
+        assert!(holds.expire(Instant::now()).is_empty());
+        assert!(status(&holds, 1).is_held_by(1));
+
+        let notices = holds.expire(Instant::now() + TTL);
+        assert_eq!(notices.len(), 1);
+        assert_eq!(notices[0].session, 1);
+        assert_eq!(notices[0].seats, vec![seat(1)]);
+        assert_eq!(status(&holds, 1), SeatStatus::Free);
+
+        let notice = expirations.try_recv().unwrap();
+        assert_eq!((notice.session, notice.seats), (1, vec![seat(1)]));
+    }
+
+    #[test]
+    fn renewed_hold_outlives_its_first_expiry() {
+        let holds = registry();
+        holds.hold_seats(1, &[seat(1)]).unwrap();
+        let first_expiry = expires_at(&holds, 1);
+
+        std::thread::sleep(Duration::from_millis(5));
+        holds.renew(1, &[seat(1)]);
+        assert!(expires_at(&holds, 1) > first_expiry);
+
+        assert!(holds.expire(first_expiry).is_empty());
+        assert!(status(&holds, 1).is_held_by(1));
+    }
+
+    #[test]
+    fn release_keeps_holds_of_other_sessions() {
+        let holds = registry();
+        holds.hold_seats(1, &[seat(1)]).unwrap();
+        holds.hold_seats(2, &[seat(2)]).unwrap();
+
+        holds.release(2, &[seat(1), seat(2)]);
+        assert!(status(&holds, 1).is_held_by(1));
+        assert_eq!(status(&holds, 2), SeatStatus::Free);
+    }
+}
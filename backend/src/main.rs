@@ -1,19 +1,156 @@
-mod client;
+mod allocator;
+mod config;
+mod events;
+mod holds;
+mod orders;
+mod payment;
+mod protocol;
 mod seat_manager;
 mod socket_manager;
-mod test;
-use crate::seat_manager::create_seats;
-use crate::test::mark_predefined_seats_as_booked;
-use socket_manager::start_socket_server;
+mod storage;
+mod venue;
+mod waiting_room;
+use crate::allocator::Allocator;
+use crate::config::ServerConfig;
+use crate::events::{load_events, spawn_lifecycle, Event, EventCatalog};
+use crate::holds::{spawn_reaper, HoldRegistry};
+use crate::orders::OrderRegistry;
+use crate::payment::MockPaymentGateway;
+use crate::seat_manager::{block_seats, create_seats};
+use crate::storage::Storage;
+use crate::waiting_room::{spawn_admitter, WaitingRoom};
+use socket_manager::{start_socket_server, ServerState};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Archivo de eventos usado cuando no se indica otro en la línea de comandos.
+const DEFAULT_EVENTS_PATH: &str = "events.toml";
 
 #[tokio::main]
 async fn main() {
-    // Create the seats
-    let seats = create_seats();
+    // Read the server settings
+    let config = match ServerConfig::from_env() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Configuración inválida: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    // Load the events and their venue layouts
+    let events_path = std::env::args()
+        .nth(1)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_EVENTS_PATH));
+    let definitions = match load_events(&events_path) {
+        Ok(definitions) => definitions,
+        Err(e) => {
+            eprintln!("Error al cargar {}: {}", events_path.display(), e);
+            std::process::exit(1);
+        }
+    };
+    for definition in &definitions {
+        let venue = &definition.venue;
+        println!(
+            "Evento cargado: {} ({}) en {}",
+            definition.name, definition.id, venue.name
+        );
+        for category in &venue.categories {
+            let sections: Vec<&str> = venue
+                .sections
+                .iter()
+                .filter(|s| s.category == category.id)
+                .map(|s| s.name.as_str())
+                .collect();
+            println!("  {}: {}", category.name, sections.join(", "));
+        }
+    }
+
+    // Open the database
+    let storage = match Storage::open(&config.db_path) {
+        Ok(storage) => Arc::new(storage),
+        Err(e) => {
+            eprintln!("Error al abrir {}: {}", config.db_path.display(), e);
+            std::process::exit(1);
+        }
+    };
+
+    let mut events = Vec::with_capacity(definitions.len());
+    for definition in definitions {
+        // Create the seats of the event
+        let seats = create_seats(&definition.venue);
+
+        // Restore the saved seats, or block the seats listed in the event on a new event
+        let restored = match storage.is_empty(&definition.id) {
+            Ok(true) => {
+                if let Err(e) = block_seats(&seats, &definition.blocked) {
+                    eprintln!(
+                        "Error al bloquear asientos del evento {}: {}",
+                        definition.id, e
+                    );
+                    std::process::exit(1);
+                }
+                storage.save_all(&definition.id, &seats)
+            }
+            Ok(false) => storage.restore(&definition.id, &seats),
+            Err(e) => Err(e),
+        };
+        if let Err(e) = restored {
+            eprintln!(
+                "Error al cargar los asientos guardados del evento {}: {}",
+                definition.id, e
+            );
+            std::process::exit(1);
+        }
+
+        // Release temporary holds once they expire
+        let holds = Arc::new(HoldRegistry::new(
+            definition.id.clone(),
+            config.hold_ttl,
+            seats.clone(),
+            Arc::clone(&storage),
+            config.distancing,
+        ));
+        spawn_reaper(Arc::clone(&holds));
+
+        events.push(Event::new(definition, seats, holds));
+    }
+
+    // Continue the order numbering from the database
+    let orders = match OrderRegistry::new(Arc::clone(&storage)) {
+        Ok(orders) => Arc::new(orders),
+        Err(e) => {
+            eprintln!("Error al cargar las órdenes guardadas: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    // Open and close the sales of each event on schedule
+    let events = Arc::new(EventCatalog::new(events));
+    for event in events.all() {
+        spawn_lifecycle(Arc::clone(event));
+    }
 
-    // Mark the burned seats as booked
-    mark_predefined_seats_as_booked(seats.clone());
+    // Let sessions in at the configured rate
+    let waiting_room = Arc::new(WaitingRoom::new(
+        config.admissions_per_minute,
+        config.admission_token_ttl,
+    ));
+    if spawn_admitter(Arc::clone(&waiting_room)).is_some() {
+        println!(
+            "Sala de espera activa: {} sesiones por minuto",
+            config.admissions_per_minute
+        );
+    }
 
     // Start the socket server
-    start_socket_server(seats).await;
+    let state = Arc::new(ServerState {
+        events,
+        waiting_room,
+        orders,
+        allocator: Arc::new(Allocator::new(config.orphan_rule, config.distancing)),
+        payments: Arc::new(MockPaymentGateway::new()),
+        staff_key: config.staff_key.clone(),
+    });
+    start_socket_server(state).await;
 }
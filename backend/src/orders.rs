@@ -0,0 +1,212 @@
+// orders.rs
+
+use crate::events::EventId;
+use crate::payment::PaymentReceipt;
+use crate::seat_manager::SeatKey;
+use crate::storage::{Purchaser, Storage, StorageError};
+use crate::venue::{CategoryId, SectionId};
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Identificador de una orden (por ejemplo, "ORD-3F9A0C1D2B4E5F60"). Es aleatorio para que
+/// no se puedan recorrer las órdenes de otros clientes.
+pub type OrderId = String;
+
+/// Identificador de una entrada emitida (por ejemplo, "TKT-3F9A0C1D2B4E5F60-1").
+pub type TicketId = String;
+
+/// Estado de una orden.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderStatus {
+    /// El cobro fue aprobado y las entradas quedaron emitidas.
+    Paid,
+}
+
+impl OrderStatus {
+    /// Nombre con el que se guarda el estado.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OrderStatus::Paid => "paid",
+        }
+    }
+
+    /// Interpreta un estado guardado.
+    pub fn parse(value: &str) -> Option<OrderStatus> {
+        match value {
+            "paid" => Some(OrderStatus::Paid),
+            _ => None,
+        }
+    }
+}
+
+/// Entrada emitida para un asiento de una orden.
+#[derive(Debug, Clone, Serialize)]
+pub struct Ticket {
+    pub id: TicketId,
+    pub section: SectionId,
+    pub row: u32,
+    pub number: u32,
+    /// Categoría del asiento al comprarlo; cuenta para los topes de compra.
+    pub category: CategoryId,
+    /// Precio cobrado por este asiento.
+    pub price: f32,
+}
+
+impl Ticket {
+    /// Asiento al que corresponde la entrada.
+    pub fn seat(&self) -> SeatKey {
+        (self.section.clone(), self.row, self.number)
+    }
+}
+
+/// Compra confirmada: quién compró qué asientos, cuándo y por cuánto.
+#[derive(Debug, Clone, Serialize)]
+pub struct Order {
+    /// Número correlativo de la orden, para listarlas en el orden en que se pagaron.
+    #[serde(skip)]
+    pub number: u64,
+    pub id: OrderId,
+    /// Clave que permite consultar la orden desde otra conexión; solo la recibe quien pagó.
+    pub access_key: String,
+    /// Evento al que corresponden las entradas.
+    pub event: EventId,
+    /// Referencia del cliente con la que el personal de soporte busca la compra. La indica
+    /// el cliente, así que no cuenta para los topes de compra.
+    pub customer: String,
+    /// Identidad del comprador en el servidor: su token de admisión o, sin sala de espera,
+    /// su conexión. Cuenta para el tope por sesión.
+    #[serde(skip)]
+    pub buyer: String,
+    /// Huella de la tarjeta con que se pagó. Cuenta para el tope por cliente.
+    #[serde(skip)]
+    pub card_fingerprint: String,
+    pub tickets: Vec<Ticket>,
+    pub total_price: f32,
+    pub transaction_id: String,
+    pub status: OrderStatus,
+    /// Segundos desde la época Unix en que se inició el pago.
+    pub created_at: u64,
+    /// Segundos desde la época Unix en que se aprobó el cobro.
+    pub paid_at: u64,
+}
+
+/// Registro de las órdenes: asigna sus identificadores y las consulta en la base de datos.
+pub struct OrderRegistry {
+    storage: Arc<Storage>,
+    next_order: AtomicU64,
+}
+
+impl OrderRegistry {
+    /// Crea el registro continuando la numeración de las órdenes ya guardadas.
+    pub fn new(storage: Arc<Storage>) -> Result<Self, StorageError> {
+        let last = storage.last_order_number()?;
+        Ok(OrderRegistry {
+            storage,
+            next_order: AtomicU64::new(last + 1),
+        })
+    }
+
+    /// Arma una orden pagada para los asientos del evento indicados con su categoría y precio.
+    /// La orden se guarda junto con la reserva definitiva de los asientos.
+    pub fn new_order(
+        &self,
+        event: &str,
+        customer: &str,
+        buyer: &str,
+        lines: &[(SeatKey, CategoryId, f32)],
+        receipt: &PaymentReceipt,
+        created_at: u64,
+    ) -> Order {
+        let number = self.next_order.fetch_add(1, Ordering::Relaxed);
+        let reference = random_token()[..16].to_uppercase();
+        let tickets: Vec<Ticket> = lines
+            .iter()
+            .enumerate()
+            .map(
+                |(index, ((section, row, number_in_row), category, price))| Ticket {
+                    id: format!("TKT-{}-{}", reference, index + 1),
+                    section: section.clone(),
+                    row: *row,
+                    number: *number_in_row,
+                    category: category.clone(),
+                    price: *price,
+                },
+            )
+            .collect();
+
+        Order {
+            number,
+            id: format!("ORD-{}", reference),
+            access_key: random_token(),
+            event: event.to_string(),
+            customer: customer.to_string(),
+            buyer: buyer.to_string(),
+            card_fingerprint: receipt.card_fingerprint.clone(),
+            total_price: tickets.iter().map(|ticket| ticket.price).sum(),
+            tickets,
+            transaction_id: receipt.transaction_id.clone(),
+            status: OrderStatus::Paid,
+            created_at,
+            paid_at: unix_now(),
+        }
+    }
+
+    /// Busca una orden por su identificador, sin verificar quién la consulta.
+    pub fn find(&self, id: &str) -> Result<Option<Order>, StorageError> {
+        self.storage.load_order(id)
+    }
+
+    /// Asientos de la categoría que el comprador ya compró en el evento.
+    pub fn purchased_by(
+        &self,
+        event: &str,
+        category: &str,
+        buyer: &str,
+    ) -> Result<u32, StorageError> {
+        self.storage
+            .purchased_seats(event, category, Purchaser::Buyer(buyer))
+    }
+
+    /// Busca las órdenes de un cliente, de la más antigua a la más reciente.
+    pub fn find_by_customer(&self, customer: &str) -> Result<Vec<Order>, StorageError> {
+        self.storage.load_customer_orders(customer)
+    }
+}
+
+/// Función auxiliar para obtener la hora actual en segundos desde la época Unix.
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Función auxiliar para generar un valor difícil de adivinar (32 dígitos hexadecimales)
+/// a partir de 16 bytes del generador aleatorio del sistema operativo.
+pub fn random_token() -> String {
+    let mut bytes = [0u8; 16];
+    getrandom::getrandom(&mut bytes)
+        .expect("el generador aleatorio del sistema operativo no está disponible");
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn random_tokens_are_hex_and_unique() {
+        let tokens: HashSet<String> = (0..1000).map(|_| random_token()).collect();
+        assert_eq!(tokens.len(), 1000);
+        for token in &tokens {
+            assert_eq!(token.len(), 32);
+            assert!(token
+                .chars()
+                .all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c)));
+        }
+    }
+}
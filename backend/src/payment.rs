@@ -0,0 +1,183 @@
+// payment.rs
+
+use crate::orders::random_token;
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::fmt;
+
+/// Datos de la tarjeta enviados por el cliente.
+/// Al depurarse solo muestran los últimos 4 dígitos del número y nunca el CVC.
+#[derive(Clone, Deserialize)]
+pub struct CardDetails {
+    pub number: String,
+    pub holder: String,
+    pub expiry: String,
+    pub cvc: String,
+}
+
+impl fmt::Debug for CardDetails {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CardDetails")
+            .field("number", &masked_number(&self.number))
+            .field("holder", &self.holder)
+            .field("expiry", &self.expiry)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Función auxiliar para ocultar el número de una tarjeta salvo sus últimos 4 dígitos.
+fn masked_number(number: &str) -> String {
+    let digits: Vec<char> = number.chars().filter(|c| c.is_ascii_digit()).collect();
+    let last: String = digits[digits.len().saturating_sub(4)..].iter().collect();
+    format!("****{}", last)
+}
+
+/// Cobro que el servidor solicita a la pasarela.
+#[derive(Debug, Clone)]
+pub struct ChargeRequest {
+    /// Referencia interna del cobro (por ejemplo, la sesión que compra).
+    pub reference: String,
+    pub amount: f32,
+    pub card: CardDetails,
+}
+
+/// Comprobante de un cobro aprobado.
+#[derive(Debug, Clone)]
+pub struct PaymentReceipt {
+    pub transaction_id: String,
+    pub amount: f32,
+    /// Huella de la tarjeta cobrada: es la misma en cada cobro con esa tarjeta sin revelar
+    /// su número. Sirve para aplicar los topes de compra por cliente.
+    pub card_fingerprint: String,
+}
+
+/// Errores posibles al cobrar o reembolsar.
+#[derive(Debug, Clone)]
+pub enum PaymentError {
+    /// La pasarela rechazó el cobro.
+    Declined(String),
+    /// Los datos de pago no son válidos.
+    InvalidDetails(String),
+}
+
+impl fmt::Display for PaymentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PaymentError::Declined(msg) => write!(f, "pago rechazado: {}", msg),
+            PaymentError::InvalidDetails(msg) => write!(f, "datos de pago inválidos: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for PaymentError {}
+
+/// Pasarela de pago a la que el servidor cobra directamente.
+#[async_trait]
+pub trait PaymentGateway: Send + Sync {
+    /// Cobra el monto indicado con la tarjeta del cliente.
+    async fn charge(&self, request: ChargeRequest) -> Result<PaymentReceipt, PaymentError>;
+
+    /// Reembolsa un cobro aprobado anteriormente.
+    async fn refund(&self, receipt: &PaymentReceipt) -> Result<(), PaymentError>;
+}
+
+/// Terminación de tarjeta que la pasarela simulada siempre rechaza.
+pub const MOCK_DECLINED_SUFFIX: &str = "0002";
+
+/// Pasarela simulada y determinista para pruebas y uso local.
+///
+/// Aprueba cualquier tarjeta de 12 a 19 dígitos con vencimiento `MM/AA` y CVC de 3 o 4
+/// dígitos, salvo las que terminan en `MOCK_DECLINED_SUFFIX`. Cada cobro recibe un
+/// identificador aleatorio, de modo que no se repiten entre reinicios del servidor.
+#[derive(Default)]
+pub struct MockPaymentGateway;
+
+impl MockPaymentGateway {
+    pub fn new() -> Self {
+        MockPaymentGateway
+    }
+}
+
+#[async_trait]
+impl PaymentGateway for MockPaymentGateway {
+    async fn charge(&self, request: ChargeRequest) -> Result<PaymentReceipt, PaymentError> {
+        let card = &request.card;
+        let number: String = card.number.chars().filter(|c| !c.is_whitespace()).collect();
+
+        if !(12..=19).contains(&number.len()) || !number.chars().all(|c| c.is_ascii_digit()) {
+            return Err(PaymentError::InvalidDetails("número de tarjeta inválido".to_string()));
+        }
+        if card.holder.trim().is_empty() {
+            return Err(PaymentError::InvalidDetails("falta el titular de la tarjeta".to_string()));
+        }
+        let expiry_valid = card.expiry.is_ascii()
+            && card.expiry.len() == 5
+            && card.expiry.as_bytes()[2] == b'/'
+            && card.expiry[..2].parse::<u32>().is_ok_and(|month| (1..=12).contains(&month))
+            && card.expiry[3..].parse::<u32>().is_ok();
+        if !expiry_valid {
+            return Err(PaymentError::InvalidDetails("vencimiento inválido".to_string()));
+        }
+        if !(3..=4).contains(&card.cvc.len()) || !card.cvc.chars().all(|c| c.is_ascii_digit()) {
+            return Err(PaymentError::InvalidDetails("CVC inválido".to_string()));
+        }
+        if !request.amount.is_finite() || request.amount <= 0.0 {
+            return Err(PaymentError::InvalidDetails("monto inválido".to_string()));
+        }
+        if number.ends_with(MOCK_DECLINED_SUFFIX) {
+            return Err(PaymentError::Declined("fondos insuficientes".to_string()));
+        }
+
+        println!(
+            "Cobro simulado de {:.2} aprobado para {}",
+            request.amount, request.reference
+        );
+        Ok(PaymentReceipt {
+            transaction_id: format!("mock-{}", random_token()),
+            amount: request.amount,
+            card_fingerprint: mock_fingerprint(&number),
+        })
+    }
+
+    async fn refund(&self, receipt: &PaymentReceipt) -> Result<(), PaymentError> {
+        println!(
+            "Reembolso simulado de {:.2} para {}",
+            receipt.amount, receipt.transaction_id
+        );
+        Ok(())
+    }
+}
+
+/// Función auxiliar para calcular la huella simulada de una tarjeta (FNV-1a de sus dígitos).
+fn mock_fingerprint(number: &str) -> String {
+    let hash = number
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+        });
+    format!("mock-{:016x}", hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn debug_shows_only_the_last_digits() {
+        let card = CardDetails {
+            number: "4111 1111 1111 1234".to_string(),
+            holder: "Ana".to_string(),
+            expiry: "12/30".to_string(),
+            cvc: "987".to_string(),
+        };
+        let request = ChargeRequest {
+            reference: "sesion-1".to_string(),
+            amount: 30.0,
+            card,
+        };
+        let text = format!("{:?}", request);
+        assert!(text.contains("****1234"));
+        assert!(!text.contains("4111"));
+        assert!(!text.contains("987"));
+    }
+}
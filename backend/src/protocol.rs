@@ -0,0 +1,401 @@
+// protocol.rs
+
+use crate::allocator::{Contiguity, SeatRequest, DEFAULT_SUGGESTIONS};
+use crate::events::{Event, EventId, EventStatus};
+use crate::orders::{Order, OrderId};
+use crate::payment::CardDetails;
+use crate::seat_manager::{SeatKey, SeatState};
+use crate::venue::{CategoryId, SeatAttributes, SectionId, Venue};
+use crate::waiting_room::AdmissionToken;
+use serde::{Deserialize, Serialize};
+
+/// Mensaje recibido del cliente junto con su identificador de solicitud opcional.
+#[derive(Debug, Deserialize)]
+pub struct ClientEnvelope {
+    pub request_id: Option<String>,
+    #[serde(flatten)]
+    pub message: ClientMessage,
+}
+
+/// Mensajes que el cliente puede enviar al servidor.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    /// Solicita sugerencias de asientos en una categoría.
+    RequestSeats(SeatQuery),
+    /// Acepta una de las sugerencias recibidas (numeradas desde 1).
+    ChooseSuggestion { suggestion_number: usize },
+    /// Rechaza todas las sugerencias pendientes.
+    RejectSuggestions,
+    /// Reserva temporalmente asientos concretos de un evento, que quedan listos para pagar.
+    /// Durante la preventa debe indicar un código que habilite todos los asientos.
+    /// Los espacios para silla de ruedas y de acompañantes solo se reservan si el mensaje
+    /// indica cuántos espacios para silla de ruedas necesita.
+    HoldSeats {
+        event: EventId,
+        seats: Vec<SeatRef>,
+        #[serde(default)]
+        access_code: Option<String>,
+        #[serde(default)]
+        wheelchair_spaces: u32,
+    },
+    /// Paga los asientos de la sugerencia aceptada con la tarjeta indicada.
+    /// `customer` identifica al comprador en la orden; si falta se usa el titular de la tarjeta.
+    Pay {
+        card: CardDetails,
+        #[serde(default)]
+        customer: Option<String>,
+    },
+    /// Consulta una orden por su identificador. Las órdenes que no pagó esta sesión
+    /// necesitan su clave de acceso.
+    GetOrder {
+        order_id: OrderId,
+        #[serde(default)]
+        access_key: Option<String>,
+    },
+    /// Consulta todas las órdenes de un cliente; solo para el personal, con su clave.
+    FindOrders { customer: String, staff_key: String },
+    /// Presenta un token de admisión vigente para salir de la fila de espera.
+    PresentToken { token: String },
+    /// Solicita el estado actual de todos los asientos de un evento (por defecto, el actual).
+    GetState {
+        #[serde(default)]
+        event: Option<EventId>,
+    },
+}
+
+impl ClientMessage {
+    /// Tipo del mensaje, tal como lo envía el cliente. Es lo único que se registra de cada
+    /// mensaje, para no escribir datos de pago en los registros.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ClientMessage::RequestSeats(_) => "request_seats",
+            ClientMessage::ChooseSuggestion { .. } => "choose_suggestion",
+            ClientMessage::RejectSuggestions => "reject_suggestions",
+            ClientMessage::HoldSeats { .. } => "hold_seats",
+            ClientMessage::Pay { .. } => "pay",
+            ClientMessage::GetOrder { .. } => "get_order",
+            ClientMessage::FindOrders { .. } => "find_orders",
+            ClientMessage::PresentToken { .. } => "present_token",
+            ClientMessage::GetState { .. } => "get_state",
+        }
+    }
+
+    /// Código de preventa que acompaña al mensaje, si indica uno.
+    pub fn access_code(&self) -> Option<&str> {
+        match self {
+            ClientMessage::RequestSeats(query) => query.access_code.as_deref(),
+            ClientMessage::HoldSeats { access_code, .. } => access_code.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Evento al que se refiere el mensaje, si indica uno.
+    pub fn event(&self) -> Option<&str> {
+        match self {
+            ClientMessage::RequestSeats(query) => Some(&query.event),
+            ClientMessage::HoldSeats { event, .. } => Some(event),
+            ClientMessage::GetState { event } => event.as_deref(),
+            _ => None,
+        }
+    }
+}
+
+/// Mensaje enviado al cliente, con el identificador de la solicitud que lo originó.
+#[derive(Debug, Serialize)]
+pub struct ServerEnvelope {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    #[serde(flatten)]
+    pub message: ServerMessage,
+}
+
+/// Mensajes que el servidor puede enviar al cliente.
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    /// Posición de la sesión en la fila de espera y segundos estimados hasta su turno.
+    QueuePosition { position: u64, eta_secs: u64 },
+    /// La sesión salió de la fila; el token permite volver a entrar sin esperar.
+    Admitted { token: String, expires_at: u64 },
+    /// Eventos a la venta, del más próximo al más lejano.
+    Events { events: Vec<EventInfo> },
+    /// El evento actual cambió de estado de venta.
+    EventStatus { event: EventId, status: EventStatus },
+    /// Descripción del recinto: categorías y secciones en orden de visualización.
+    Venue(VenueInfo),
+    /// Estado completo de los asientos de un evento.
+    SeatStates {
+        event: EventId,
+        seats: Vec<SeatState>,
+    },
+    /// Asientos de un evento que cambiaron de estado desde el último mensaje.
+    SeatUpdates {
+        event: EventId,
+        seats: Vec<SeatState>,
+    },
+    /// Sugerencias encontradas para la última solicitud.
+    Suggestions { suggestions: Vec<SeatSuggestion> },
+    /// Los asientos de la sugerencia indicada quedaron reservados temporalmente;
+    /// las demás se descartaron.
+    SuggestionAccepted { suggestion_number: usize },
+    /// Todas las sugerencias pendientes fueron descartadas.
+    SuggestionsRejected,
+    /// Los asientos elegidos quedaron reservados temporalmente, con sus precios.
+    SeatsHeld { seats: Vec<SeatInfo>, total_price: f32 },
+    /// La reserva temporal de estos asientos venció y volvieron a estar libres.
+    HoldExpired { seats: Vec<SeatRef> },
+    /// El cobro fue aprobado, los asientos quedaron reservados y se emitió la orden.
+    PaymentAccepted {
+        seats: Vec<SeatRef>,
+        total_price: f32,
+        transaction_id: String,
+        order: Order,
+    },
+    /// El cobro fue rechazado y los asientos fueron liberados.
+    PaymentDeclined { reason: String },
+    /// Orden consultada con sus entradas.
+    Order { order: Order },
+    /// Órdenes de un cliente.
+    Orders { orders: Vec<Order> },
+    /// La solicitud no pudo procesarse.
+    Error { code: ErrorCode, message: String },
+}
+
+/// Pedido de sugerencias de asientos con sus filtros opcionales.
+#[derive(Debug, Deserialize)]
+pub struct SeatQuery {
+    /// Evento en el que se buscan los asientos.
+    pub event: EventId,
+    pub category: CategoryId,
+    pub seat_count: u32,
+    /// Solo ofrecer bloques con los asientos juntos.
+    #[serde(default)]
+    pub together: bool,
+    /// Cantidad de sugerencias a ofrecer; por defecto `DEFAULT_SUGGESTIONS`.
+    #[serde(default)]
+    pub max_suggestions: Option<usize>,
+    /// Precio total máximo de cada sugerencia.
+    #[serde(default)]
+    pub max_total_price: Option<f32>,
+    /// Secciones cuyas sugerencias se ofrecen primero.
+    #[serde(default)]
+    pub preferred_sections: Vec<SectionId>,
+    /// Secciones que no deben sugerirse.
+    #[serde(default)]
+    pub excluded_sections: Vec<SectionId>,
+    /// Buscar en categorías inferiores si la pedida no tiene lugar; por defecto sí.
+    #[serde(default)]
+    pub allow_downgrade: Option<bool>,
+    /// Agregar ofertas de la categoría superior a la pedida.
+    #[serde(default)]
+    pub include_upgrades: bool,
+    /// Espacios para silla de ruedas; los demás asientos pedidos son acompañantes contiguos.
+    #[serde(default)]
+    pub wheelchair_spaces: u32,
+    /// Código de preventa; necesario mientras el evento está en preventa.
+    #[serde(default)]
+    pub access_code: Option<String>,
+}
+
+impl From<SeatQuery> for SeatRequest {
+    fn from(query: SeatQuery) -> Self {
+        SeatRequest {
+            category: query.category,
+            seats_amount: query.seat_count,
+            together: query.together,
+            max_suggestions: query.max_suggestions.unwrap_or(DEFAULT_SUGGESTIONS),
+            max_total_price: query.max_total_price,
+            preferred_sections: query.preferred_sections,
+            excluded_sections: query.excluded_sections,
+            allow_downgrade: query.allow_downgrade.unwrap_or(true),
+            include_upgrades: query.include_upgrades,
+            wheelchair_spaces: query.wheelchair_spaces,
+            // El código se valida contra el evento antes de buscar
+            presale: None,
+        }
+    }
+}
+
+/// Códigos de error estables que el cliente puede interpretar.
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    InvalidMessage,
+    NotAdmitted,
+    InvalidAdmissionToken,
+    UnknownEvent,
+    NotOnSale,
+    InvalidAccessCode,
+    NotUnlocked,
+    UnknownCategory,
+    InvalidSeatCount,
+    PurchaseLimit,
+    InvalidSuggestionCount,
+    InvalidPriceLimit,
+    UnknownSection,
+    NoPendingSuggestions,
+    InvalidSuggestion,
+    NothingToPay,
+    HoldLost,
+    InvalidSeats,
+    AccessibleSeat,
+    UnknownSeats,
+    SeatsUnavailable,
+    UnknownOrder,
+    Forbidden,
+    Internal,
+}
+
+/// Referencia a un asiento concreto.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeatRef {
+    pub section: SectionId,
+    pub row: u32,
+    pub number: u32,
+}
+
+impl SeatRef {
+    /// Identificador del asiento en el inventario.
+    pub fn key(&self) -> SeatKey {
+        (self.section.clone(), self.row, self.number)
+    }
+}
+
+impl From<&SeatKey> for SeatRef {
+    fn from((section, row, number): &SeatKey) -> Self {
+        SeatRef {
+            section: section.clone(),
+            row: *row,
+            number: *number,
+        }
+    }
+}
+
+/// Sugerencia de asientos con sus precios.
+#[derive(Debug, Serialize)]
+pub struct SeatSuggestion {
+    pub suggestion_number: usize,
+    pub seats: Vec<SeatInfo>,
+    pub total_price: f32,
+    /// Cómo quedan repartidos los asientos.
+    pub contiguity: Contiguity,
+    /// Categoría de los asientos.
+    pub category: CategoryId,
+    /// Es una oferta de la categoría superior a la pedida.
+    pub upgrade: bool,
+    /// En las mejoras, cuánto más cuesta que la primera sugerencia de la categoría pedida.
+    pub price_difference: Option<f32>,
+}
+
+/// Asiento sugerido con su precio.
+#[derive(Debug, Serialize)]
+pub struct SeatInfo {
+    pub section: SectionId,
+    pub row: u32,
+    pub number: u32,
+    pub price: f32,
+    /// Características especiales del asiento.
+    pub attributes: SeatAttributes,
+}
+
+/// Descripción pública de un evento y de su recinto.
+#[derive(Debug, Serialize)]
+pub struct EventInfo {
+    pub id: EventId,
+    pub name: String,
+    /// Segundos desde la época Unix en que empieza el evento.
+    pub starts_at: u64,
+    pub status: EventStatus,
+    /// Inicio de la preventa, de la venta general y cierre de la venta, en segundos
+    /// desde la época Unix.
+    pub presale_at: Option<u64>,
+    pub on_sale_at: Option<u64>,
+    pub closes_at: u64,
+    pub venue: VenueInfo,
+}
+
+impl From<&Event> for EventInfo {
+    fn from(event: &Event) -> Self {
+        EventInfo {
+            id: event.id.clone(),
+            name: event.name.clone(),
+            starts_at: event.starts_at,
+            status: event.status(),
+            presale_at: event.schedule.presale_at,
+            on_sale_at: event.schedule.on_sale_at,
+            closes_at: event.schedule.closes_at,
+            venue: VenueInfo::from(event.venue.as_ref()),
+        }
+    }
+}
+
+/// Descripción pública del recinto.
+#[derive(Debug, Serialize)]
+pub struct VenueInfo {
+    pub name: String,
+    pub categories: Vec<CategoryInfo>,
+    pub sections: Vec<SectionInfo>,
+}
+
+/// Categoría tal como se presenta al cliente.
+#[derive(Debug, Serialize)]
+pub struct CategoryInfo {
+    pub id: CategoryId,
+    pub name: String,
+    /// Asientos de la categoría que se pueden comprar en una orden, si tiene tope.
+    pub max_per_order: Option<u32>,
+}
+
+/// Sección tal como se presenta al cliente.
+#[derive(Debug, Serialize)]
+pub struct SectionInfo {
+    pub id: SectionId,
+    pub name: String,
+    pub category: CategoryId,
+}
+
+impl From<&Venue> for VenueInfo {
+    fn from(venue: &Venue) -> Self {
+        VenueInfo {
+            name: venue.name.clone(),
+            categories: venue
+                .categories
+                .iter()
+                .map(|c| CategoryInfo {
+                    id: c.id.clone(),
+                    name: c.name.clone(),
+                    max_per_order: c.limits.per_order,
+                })
+                .collect(),
+            sections: venue
+                .sections
+                .iter()
+                .map(|s| SectionInfo {
+                    id: s.id.clone(),
+                    name: s.name.clone(),
+                    category: s.category.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<AdmissionToken> for ServerMessage {
+    fn from(admission: AdmissionToken) -> Self {
+        ServerMessage::Admitted {
+            token: admission.token,
+            expires_at: admission.expires_at,
+        }
+    }
+}
+
+impl ServerMessage {
+    /// Construye un mensaje de error con su código y descripción.
+    pub fn error(code: ErrorCode, message: impl Into<String>) -> Self {
+        ServerMessage::Error {
+            code,
+            message: message.into(),
+        }
+    }
+}
@@ -1,441 +1,571 @@
 // seat_manager.rs
 
 use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
-use serde::{Serialize, Deserialize};
+use std::fmt;
+use std::sync::{Arc, LockResult, Mutex, MutexGuard};
+use std::time::Instant;
+use serde::Serialize;
+use tokio::sync::broadcast;
+use crate::holds::SessionId;
+use crate::orders::OrderId;
+use crate::storage::StorageError;
+use crate::venue::{SeatAttributes, Section, SectionId, Venue};
+
+/// Identificador de un asiento: sección, fila y número.
+pub type SeatKey = (SectionId, u32, u32);
+
+/// Mapa compartido con todos los asientos del recinto.
+pub type SeatMap = Arc<SeatInventory>;
+
+/// Cantidad de cambios que se conservan para los suscriptores más lentos.
+const UPDATES_CAPACITY: usize = 1024;
+
+/// Inventario de asientos que publica cada cambio de estado a sus suscriptores.
+pub struct SeatInventory {
+    seats: Mutex<HashMap<SeatKey, Seat>>,
+    updates: broadcast::Sender<SeatState>,
+}
+
+impl SeatInventory {
+    pub fn new(seats: HashMap<SeatKey, Seat>) -> Self {
+        let (updates, _) = broadcast::channel(UPDATES_CAPACITY);
+        SeatInventory {
+            seats: Mutex::new(seats),
+            updates,
+        }
+    }
+
+    /// Bloquea el mapa de asientos para leerlo.
+    pub fn lock(&self) -> LockResult<MutexGuard<'_, HashMap<SeatKey, Seat>>> {
+        self.seats.lock()
+    }
+
+    /// Crea un receptor que recibirá cada cambio de estado de un asiento.
+    pub fn subscribe(&self) -> broadcast::Receiver<SeatState> {
+        self.updates.subscribe()
+    }
+}
+
+/// Estado de un asiento.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SeatStatus {
+    /// Disponible para cualquier cliente.
+    Free,
+    /// Reservado temporalmente por una sesión hasta `expires_at`.
+    Held { session: SessionId, expires_at: Instant },
+    /// Vendido en la orden indicada.
+    Booked { order: OrderId },
+    /// Fuera de venta por el motivo indicado.
+    Blocked { reason: String },
+    /// Separación que el modo de distanciamiento dejó vacía junto a los asientos de la orden.
+    Buffer { order: OrderId },
+}
+
+/// Cambio de estado que la máquina de estados de los asientos no permite.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IllegalTransition {
+    pub from: char,
+    pub to: char,
+}
+
+impl SeatStatus {
+    /// Código de una letra con el que se publica el estado a los clientes:
+    /// 'F' = Libre, 'R' = Reservado temporalmente, 'B' = Reservado, 'X' = Bloqueado,
+    /// 'D' = Separación por distanciamiento.
+    pub fn code(&self) -> char {
+        match self {
+            SeatStatus::Free => 'F',
+            SeatStatus::Held { .. } => 'R',
+            SeatStatus::Booked { .. } => 'B',
+            SeatStatus::Blocked { .. } => 'X',
+            SeatStatus::Buffer { .. } => 'D',
+        }
+    }
+
+    pub fn is_free(&self) -> bool {
+        matches!(self, SeatStatus::Free)
+    }
+
+    /// Indica si el asiento está ocupado por un comprador (reservado temporalmente o vendido).
+    pub fn is_taken(&self) -> bool {
+        matches!(self, SeatStatus::Held { .. } | SeatStatus::Booked { .. })
+    }
+
+    /// Indica si el asiento está reservado temporalmente por la sesión.
+    pub fn is_held_by(&self, session: SessionId) -> bool {
+        matches!(self, SeatStatus::Held { session: owner, .. } if *owner == session)
+    }
+
+    /// Verifica que el asiento pueda pasar de este estado a `next`.
+    ///
+    /// - Libre: puede reservarse temporalmente, bloquearse o quedar como separación.
+    /// - Reservado temporalmente: puede liberarse, renovarse por la misma sesión o venderse.
+    /// - Bloqueado y separación: solo pueden volver a quedar libres.
+    /// - Vendido: es definitivo.
+    pub fn transition(&self, next: &SeatStatus) -> Result<(), IllegalTransition> {
+        let legal = match (self, next) {
+            (
+                SeatStatus::Free,
+                SeatStatus::Held { .. } | SeatStatus::Blocked { .. } | SeatStatus::Buffer { .. },
+            ) => true,
+            (SeatStatus::Held { .. }, SeatStatus::Free | SeatStatus::Booked { .. }) => true,
+            (SeatStatus::Held { session, .. }, SeatStatus::Held { session: next_session, .. }) => {
+                session == next_session
+            }
+            (SeatStatus::Blocked { .. } | SeatStatus::Buffer { .. }, SeatStatus::Free) => true,
+            _ => false,
+        };
+        if legal {
+            Ok(())
+        } else {
+            Err(IllegalTransition {
+                from: self.code(),
+                to: next.code(),
+            })
+        }
+    }
+}
+
+/// Separación mínima entre los grupos de compradores (modo de distanciamiento).
+/// Con ambos valores en cero el modo está desactivado.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Distancing {
+    /// Asientos vacíos a cada lado de un grupo, dentro de su fila.
+    pub seats: u32,
+    /// Filas vacías delante y detrás de un grupo, a lo ancho del grupo y de su separación.
+    pub rows: u32,
+}
+
+impl Distancing {
+    pub fn is_enabled(&self) -> bool {
+        self.seats > 0 || self.rows > 0
+    }
+
+    /// Asientos que deben quedar vacíos alrededor de un asiento ocupado (sin incluirlo).
+    fn zone<'a>(&self, (section, row, number): &'a SeatKey) -> impl Iterator<Item = SeatKey> + 'a {
+        let (rows, seats) = (self.rows, self.seats);
+        (row.saturating_sub(rows).max(1)..=row.saturating_add(rows)).flat_map(move |r| {
+            (number.saturating_sub(seats).max(1)..=number.saturating_add(seats))
+                .filter(move |&n| (r, n) != (*row, *number))
+                .map(move |n| (section.clone(), r, n))
+        })
+    }
+
+    /// Indica si el asiento está dentro de la separación de algún asiento ocupado.
+    pub fn is_crowded(&self, seats: &HashMap<SeatKey, Seat>, seat: &SeatKey) -> bool {
+        self.is_enabled()
+            && self
+                .zone(seat)
+                .any(|key| seats.get(&key).is_some_and(|s| s.status.is_taken()))
+    }
+
+    /// Asientos libres que deben quedar como separación alrededor de un grupo.
+    pub fn buffer_around(&self, seats: &HashMap<SeatKey, Seat>, group: &[SeatKey]) -> Vec<SeatKey> {
+        if !self.is_enabled() {
+            return Vec::new();
+        }
+        let mut buffer: Vec<SeatKey> = group
+            .iter()
+            .flat_map(|seat| self.zone(seat))
+            .filter(|key| !group.contains(key))
+            .filter(|key| seats.get(key).is_some_and(|s| s.status.is_free()))
+            .collect();
+        buffer.sort();
+        buffer.dedup();
+        buffer
+    }
+}
 
 /// Estructura que representa un asiento.
 #[derive(Debug)]
 pub struct Seat {
     pub number: u32,
-    pub section: Section,
+    pub section: SectionId,
     pub row: u32,
     pub visibility: f32,
     pub price: f32,
-    pub booked: char, // 'B' = Reservado, 'R' = Reservado temporalmente, 'F' = Libre
+    /// Características especiales definidas en el recinto.
+    pub attributes: SeatAttributes,
+    pub status: SeatStatus,
 }
 
 /// Estructura para serializar el estado del asiento
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 pub struct SeatState {
-    pub section: Section,
+    pub section: SectionId,
     pub row: u32,
     pub number: u32,
+    /// Código del estado, ver `SeatStatus::code`.
     pub booked: char,
 }
 
-/// Enumeración que representa las diferentes categorías.
-#[derive(Debug, Hash, Eq, PartialEq, Clone, Copy, Serialize, Deserialize)]
-pub enum Category {
-    VIP,
-    Business,
-    Economy,
-}
-
-impl Category {
-    /// Función para obtener las secciones asociadas a una categoría.
-    pub fn sections(&self) -> Vec<Section> {
-        match self {
-            Category::VIP => vec![Section::A1, Section::B1, Section::C1],
-            Category::Business => vec![
-                Section::A2, Section::B2, Section::C2,
-                Section::A3, Section::B3, Section::C3,
-            ],
-            Category::Economy => vec![Section::D, Section::E, Section::F],
+impl From<&Seat> for SeatState {
+    fn from(seat: &Seat) -> Self {
+        SeatState {
+            section: seat.section.clone(),
+            row: seat.row,
+            number: seat.number,
+            booked: seat.status.code(),
         }
     }
+}
 
-    /// Función para obtener la categoría inferior.
-    pub fn lower_category(&self) -> Option<Category> {
-        match self {
-            Category::VIP => Some(Category::Business),
-            Category::Business => Some(Category::Economy),
-            Category::Economy => None,
-        }
+/// Función para crear el conjunto de asientos a partir de la definición del recinto.
+/// Retorna un `SeatMap` que contiene todos los asientos.
+pub fn create_seats(venue: &Venue) -> SeatMap {
+    let mut seats = HashMap::new();
+
+    for section in &venue.sections {
+        add_seats(&mut seats, section);
     }
-}
 
-/// Enumeración que representa las diferentes secciones en la disposición de asientos.
-#[derive(Debug, Hash, Eq, PartialEq, PartialOrd, Ord, Clone, Copy, Serialize, Deserialize)]
-pub enum Section {
-    A1,
-    B1,
-    C1,
-    A2,
-    B2,
-    C2,
-    A3,
-    B3,
-    C3,
-    D,
-    E,
-    F,
+    Arc::new(SeatInventory::new(seats))
 }
 
-/// Implementación de métodos para la enumeración Section.
-impl Section {
-    /// Función para obtener todas las secciones.
-    pub fn all_sections() -> Vec<Section> {
-        vec![
-            Section::A1,
-            Section::B1,
-            Section::C1,
-            Section::A2,
-            Section::B2,
-            Section::C2,
-            Section::A3,
-            Section::B3,
-            Section::C3,
-            Section::D,
-            Section::E,
-            Section::F,
-        ]
+/// Función para bloquear asientos libres con su motivo, sin guardarlos; quien la llama
+/// guarda después el inventario completo.
+pub fn block_seats(seats: &SeatMap, blocked: &[(SeatKey, String)]) -> Result<(), SeatError> {
+    for (key, reason) in blocked {
+        let status = SeatStatus::Blocked {
+            reason: reason.clone(),
+        };
+        transition_seats(
+            seats,
+            std::slice::from_ref(key),
+            SeatStatus::is_free,
+            &status,
+            || Ok(()),
+        )?;
     }
+    Ok(())
 }
 
-/// Función para crear el conjunto de asientos.
-/// Retorna un `Arc<Mutex<HashMap<...>>>` que contiene todos los asientos.
-pub fn create_seats() -> Arc<Mutex<HashMap<(Section, u32, u32), Seat>>> {
-    let mut seats = HashMap::new();
-
-    let sections_vip = vec![Section::A1, Section::B1, Section::C1]; // VIP
-    let sections_business_1 = vec![Section::A2, Section::B2, Section::C2]; // Business
-    let sections_business_2 = vec![Section::A3, Section::B3, Section::C3]; // Business
-    let sections_economy = vec![Section::D, Section::E, Section::F]; // Economy
-
-    add_seats(&mut seats, &sections_vip, 1..=2, 1..=5, 100.0, 150.0);
-    add_seats(&mut seats, &sections_business_1, 1..=4, 1..=6, 90.0, 90.0);
-    add_seats(&mut seats, &sections_business_2, 1..=4, 1..=6, 80.0, 80.0);
-    add_seats(&mut seats, &sections_economy, 1..=4, 1..=8, 70.0, 30.0);
+/// Función auxiliar para añadir los asientos de una sección a la disposición.
+/// Modifica el `HashMap` proporcionado con las filas y números definidos en la sección.
+fn add_seats(seats: &mut HashMap<SeatKey, Seat>, section: &Section) {
+    for (index, &seat_count) in section.rows.iter().enumerate() {
+        let row = index as u32 + 1;
+        for number in 1..=seat_count {
+            seats.insert(
+                (section.id.clone(), row, number),
+                Seat {
+                    number,
+                    section: section.id.clone(),
+                    row,
+                    visibility: section.visibility,
+                    price: section.price,
+                    attributes: section.seat_attributes(row, number),
+                    status: SeatStatus::Free,
+                },
+            );
+        }
+    }
+}
 
-    Arc::new(Mutex::new(seats))
+/// Errores al cambiar el estado de un grupo de asientos.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SeatError {
+    /// Los asientos indicados no existen en el recinto.
+    UnknownSeats(Vec<SeatKey>),
+    /// Los asientos indicados no están en el estado esperado (por ejemplo, otro cliente ya los tomó).
+    Unavailable(Vec<SeatKey>),
+    /// Los asientos indicados quedan dentro de la separación de otro grupo (modo de distanciamiento).
+    Crowded(Vec<SeatKey>),
+    /// La máquina de estados no permite el cambio pedido.
+    Illegal(SeatKey, IllegalTransition),
+    /// El cambio no pudo guardarse; el estado en memoria no se modificó.
+    Storage(String),
+    /// Guardar la venta superaría un tope de compra; el estado en memoria no se modificó.
+    Limit(String),
 }
 
-/// Función auxiliar para añadir asientos a la disposición.
-/// Modifica el `HashMap` proporcionado con las secciones, filas y números especificados.
-fn add_seats(
-    seats: &mut HashMap<(Section, u32, u32), Seat>,
-    sections: &[Section],
-    row_range: std::ops::RangeInclusive<u32>,
-    number_range: std::ops::RangeInclusive<u32>,
-    visibility: f32,
-    price: f32,
-) {
-    for &section in sections {
-        for row in row_range.clone() {
-            for number in number_range.clone() {
-                seats.insert(
-                    (section, row, number),
-                    Seat {
-                        number,
-                        section,
-                        row,
-                        visibility,
-                        price,
-                        booked: 'F',
-                    },
-                );
+impl fmt::Display for SeatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SeatError::UnknownSeats(seats) => {
+                write!(f, "los asientos {} no existen", seat_names(seats))
+            }
+            SeatError::Unavailable(seats) => {
+                write!(f, "los asientos {} no están disponibles", seat_names(seats))
             }
+            SeatError::Crowded(seats) => write!(
+                f,
+                "los asientos {} quedan dentro de la separación de otro grupo",
+                seat_names(seats)
+            ),
+            SeatError::Illegal((section, row, number), IllegalTransition { from, to }) => write!(
+                f,
+                "el asiento {}-{}-{} no puede pasar de '{}' a '{}'",
+                section, row, number, from, to
+            ),
+            SeatError::Storage(msg) => write!(f, "no se pudo guardar el cambio: {}", msg),
+            SeatError::Limit(msg) => f.write_str(msg),
         }
     }
 }
 
-/// Función para encontrar sugerencias de asientos según las nuevas especificaciones.
-pub fn find_seats_suggestions_by_category(
-    seats_amount: u32,
-    category: Category,
-    seats: Arc<Mutex<HashMap<(Section, u32, u32), Seat>>>,
-) -> Vec<Vec<(Section, u32, u32)>> {
-    // Intentar encontrar sugerencias en la categoría solicitada.
-    let mut suggestions = find_seats_in_category(seats_amount, category, Arc::clone(&seats));
-
-    if !suggestions.is_empty() {
-        // Si se encontraron sugerencias, retornarlas sin ejecutar métodos alternativos.
-        return suggestions;
-    } else {
-        // Si no se encontraron sugerencias, intentar combinar asientos dentro de la categoría.
-        suggestions = find_combined_seats_in_category(seats_amount, category, Arc::clone(&seats));
-
-        if !suggestions.is_empty() {
-            return suggestions;
-        } else {
-            // Si aún no se encontraron sugerencias, buscar en categorías inferiores.
-            let mut current_category = category;
-            while let Some(lower_category) = current_category.lower_category() {
-                current_category = lower_category;
-                suggestions = find_seats_in_category(seats_amount, current_category, Arc::clone(&seats));
-
-                if !suggestions.is_empty() {
-                    return suggestions;
-                }
-
-                // Intentar combinaciones en la categoría inferior.
-                suggestions = find_combined_seats_in_category(seats_amount, current_category, Arc::clone(&seats));
-
-                if !suggestions.is_empty() {
-                    return suggestions;
-                }
-            }
+impl From<StorageError> for SeatError {
+    fn from(e: StorageError) -> Self {
+        match e {
+            StorageError::Limit(msg) => SeatError::Limit(msg),
+            e => SeatError::Storage(e.to_string()),
         }
     }
-
-    // Si no se encontraron sugerencias en ninguna categoría, retornar un vector vacío.
-    suggestions
 }
 
-/// Función para encontrar sugerencias de asientos en una categoría específica.
-fn find_seats_in_category(
-    seats_amount: u32,
-    category: Category,
-    seats: Arc<Mutex<HashMap<(Section, u32, u32), Seat>>>,
-) -> Vec<Vec<(Section, u32, u32)>> {
-    let sections = category.sections();
+/// Función auxiliar para nombrar asientos como `sección-fila-número`.
+fn seat_names(seats: &[SeatKey]) -> String {
+    seats
+        .iter()
+        .map(|(section, row, number)| format!("{}-{}-{}", section, row, number))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
 
-    // Contar los asientos disponibles en cada sección sin mantener el bloqueo
-    let section_counts = {
-        let seats_guard = seats.lock().unwrap();
-        let counts: Vec<(Section, u32)> = sections.iter().map(|&section| {
-            let count = seats_guard.iter().filter(|(&(sec, _, _), seat)| {
-                sec == section && seat.booked == 'F'
-            }).count() as u32;
-            (section, count)
-        }).collect();
-        counts
-    };
-
-    // Ordenar las secciones por la mayor cantidad de asientos disponibles
-    let mut section_counts = section_counts;
-    section_counts.sort_by(|a, b| b.1.cmp(&a.1));
-
-    let mut suggestions = Vec::new();
-    let mut visited_sections = Vec::new();
-
-    // Buscar asientos en secciones con más disponibilidad
-    for &(section, _) in &section_counts {
-        let available_seats = find_seats_by_section(
-            seats_amount,
-            section,
-            Arc::clone(&seats),
-            &mut visited_sections,
-        );
+impl std::error::Error for SeatError {}
+
+/// Función para cambiar de estado un grupo de asientos de forma atómica.
+/// Solo cambia los asientos si todos existen, cumplen `expected` y la máquina de estados
+/// permite pasar a `next`; en caso contrario no modifica ninguno y retorna el conflicto.
+///
+/// `persist` se ejecuta con el mapa bloqueado antes de aplicar el cambio en memoria,
+/// de modo que un cambio que no pudo guardarse tampoco se aplica.
+pub fn transition_seats<P, F>(
+    seats: &SeatMap,
+    keys: &[SeatKey],
+    expected: P,
+    next: &SeatStatus,
+    persist: F,
+) -> Result<(), SeatError>
+where
+    P: Fn(&SeatStatus) -> bool,
+    F: FnOnce() -> Result<(), StorageError>,
+{
+    transition_seats_apart(seats, keys, expected, Distancing::default(), next, persist)
+}
 
-        if !available_seats.is_empty() {
-            suggestions.push(available_seats);
-        }
+/// Función para cambiar de estado un grupo de asientos como `transition_seats`, rechazando
+/// además los asientos que queden dentro de la separación de otro grupo según `distancing`.
+/// La separación se revisa con el mapa bloqueado, de modo que dos grupos no pueden quedar
+/// juntos aunque se reserven al mismo tiempo.
+pub fn transition_seats_apart<P, F>(
+    seats: &SeatMap,
+    keys: &[SeatKey],
+    expected: P,
+    distancing: Distancing,
+    next: &SeatStatus,
+    persist: F,
+) -> Result<(), SeatError>
+where
+    P: Fn(&SeatStatus) -> bool,
+    F: FnOnce() -> Result<(), StorageError>,
+{
+    let mut seats_guard = seats.lock().unwrap();
 
-        if suggestions.len() == 3 {
-            break;
-        }
+    let unknown: Vec<SeatKey> = keys
+        .iter()
+        .filter(|key| !seats_guard.contains_key(*key))
+        .cloned()
+        .collect();
+    if !unknown.is_empty() {
+        return Err(SeatError::UnknownSeats(unknown));
     }
 
-    // Filtrar sugerencias que tengan la cantidad requerida de asientos
-    suggestions.retain(|s| s.len() as u32 == seats_amount);
-
-    suggestions
-}
+    let unavailable: Vec<SeatKey> = keys
+        .iter()
+        .filter(|key| !expected(&seats_guard[*key].status))
+        .cloned()
+        .collect();
+    if !unavailable.is_empty() {
+        return Err(SeatError::Unavailable(unavailable));
+    }
 
-/// Función para combinar asientos de diferentes secciones dentro de una categoría.
-fn find_combined_seats_in_category(
-    seats_amount: u32,
-    category: Category,
-    seats: Arc<Mutex<HashMap<(Section, u32, u32), Seat>>>,
-) -> Vec<Vec<(Section, u32, u32)>> {
-    let sections = category.sections();
+    let crowded: Vec<SeatKey> = keys
+        .iter()
+        .filter(|key| distancing.is_crowded(&seats_guard, key))
+        .cloned()
+        .collect();
+    if !crowded.is_empty() {
+        return Err(SeatError::Crowded(crowded));
+    }
 
-    let seats_guard = seats.lock().unwrap();
+    for key in keys {
+        if let Err(illegal) = seats_guard[key].status.transition(next) {
+            return Err(SeatError::Illegal(key.clone(), illegal));
+        }
+    }
 
-    let mut all_available_seats = Vec::new();
+    persist()?;
 
-    // Recolectar todos los asientos disponibles en la categoría
-    for &section in &sections {
-        for (&(sec, row, number), seat) in seats_guard.iter() {
-            if sec == section && seat.booked == 'F' {
-                all_available_seats.push((sec, row, number));
-            }
+    for key in keys {
+        let seat = seats_guard.get_mut(key).unwrap();
+        let changed = seat.status.code() != next.code();
+        seat.status = next.clone();
+        // Las renovaciones no cambian lo que ven los clientes
+        if changed {
+            let _ = seats.updates.send(SeatState::from(&*seat));
         }
     }
+    Ok(())
+}
 
-    // Ordenar los asientos por sección, fila y número
-    all_available_seats.sort();
-
-    // Generar combinaciones posibles
-    let mut suggestions = Vec::new();
+/// Función para obtener el estado actual de todos los asientos
+pub fn get_seat_states(
+    seats: SeatMap,
+) -> Vec<SeatState> {
+    let seats_guard = seats.lock().unwrap();
+    let mut seat_states = Vec::new();
 
-    // Intentar generar hasta 3 sugerencias
-    for _ in 0..3 {
-        if all_available_seats.len() as u32 >= seats_amount {
-            let suggestion = all_available_seats.iter().take(seats_amount as usize).cloned().collect();
-            suggestions.push(suggestion);
-            all_available_seats.drain(0..seats_amount as usize);
-        } else {
-            break;
-        }
+    for seat in seats_guard.values() {
+        seat_states.push(SeatState::from(seat));
     }
 
-    suggestions
+    seat_states
 }
-
-/// Función para encontrar asientos disponibles en una sección específica.
-fn find_seats_by_section(
-    seats_amount: u32,
-    section: Section,
-    seats: Arc<Mutex<HashMap<(Section, u32, u32), Seat>>>,
-    visited_sections: &mut Vec<Section>,
-) -> Vec<(Section, u32, u32)> {
-    // Evitar visitar la misma sección varias veces
-    if !visited_sections.contains(&section) {
-        visited_sections.push(section);
-    }
-
-    let seats_guard = seats.lock().unwrap(); // Bloquear el Mutex
-    let mut available_seats = Vec::new();
-    let mut best_seats_options = Vec::new();
-
-    // Obtener todas las filas en la sección
-    let rows: Vec<u32> = seats_guard.keys()
-        .filter(|&&(sec, _, _)| sec == section)
-        .map(|&(_, row, _)| row)
+/// Función para crear un inventario de prueba: la fila 1 de la sección F, con un asiento de
+/// 30.0 por cada estado indicado, numerados desde 1.
+#[cfg(test)]
+pub fn row_of_seats(statuses: &[SeatStatus]) -> SeatMap {
+    let seats = statuses
+        .iter()
+        .zip(1..)
+        .map(|(status, number)| {
+            let seat = Seat {
+                number,
+                section: "F".to_string(),
+                row: 1,
+                visibility: 1.0,
+                price: 30.0,
+                attributes: SeatAttributes::default(),
+                status: status.clone(),
+            };
+            (("F".to_string(), 1, number), seat)
+        })
         .collect();
-    let max_row = *rows.iter().max().unwrap_or(&0);
+    Arc::new(SeatInventory::new(seats))
+}
 
-    // Buscar asientos disponibles en la sección
-    for current_row in 1..=max_row {
-        let mut row_seats = Vec::new();
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        // Obtener todos los números de asiento en la fila actual
-        let numbers: Vec<u32> = seats_guard.keys()
-            .filter(|&&(sec, row, _)| sec == section && row == current_row)
-            .map(|&(_, _, number)| number)
-            .collect();
-        let max_number = *numbers.iter().max().unwrap_or(&0);
-
-        for number in 1..=max_number {
-            if let Some(seat) = seats_guard.get(&(section, current_row, number)) {
-                if seat.booked == 'F' {
-                    // Verificar si el asiento ya está en row_seats
-                    if !row_seats.contains(&(section, current_row, number)) {
-                        row_seats.push((section, current_row, number));
-                    }
-                    if row_seats.len() >= seats_amount as usize {
-                        break;
-                    }
-                } else {
-                    if !row_seats.is_empty() {
-                        best_seats_options.push(row_seats.clone());
-                        row_seats.clear();
-                    }
-                }
-            }
+    fn held(session: SessionId) -> SeatStatus {
+        SeatStatus::Held {
+            session,
+            expires_at: Instant::now(),
         }
+    }
 
-        // Si encontramos suficientes asientos en esta fila
-        if row_seats.len() >= seats_amount as usize {
-            available_seats.extend(row_seats);
-            break;
-        } else {
-            if !row_seats.is_empty() {
-                best_seats_options.push(row_seats.clone());
-            }
+    fn booked() -> SeatStatus {
+        SeatStatus::Booked {
+            order: "ORD-1".to_string(),
         }
     }
 
-    // Si no se encontraron suficientes asientos en una fila, combinar opciones
-    if available_seats.len() < seats_amount as usize {
-        for option in best_seats_options {
-            for seat in option {
-                if !available_seats.contains(&seat) {
-                    available_seats.push(seat);
-                }
-                if available_seats.len() >= seats_amount as usize {
-                    break;
-                }
-            }
-            if available_seats.len() >= seats_amount as usize {
-                break;
-            }
+    fn blocked() -> SeatStatus {
+        SeatStatus::Blocked {
+            reason: "vendido fuera del sistema".to_string(),
         }
     }
 
-    // Si aún no se alcanzó la cantidad requerida, buscar en filas adyacentes
-    if available_seats.len() < seats_amount as usize {
-        let additional_seats = find_additional_seats_in_section(
-            seats_amount as usize - available_seats.len(),
-            section,
-            &seats_guard,
-            &available_seats,
-        );
-        available_seats.extend(additional_seats);
+    fn buffer() -> SeatStatus {
+        SeatStatus::Buffer {
+            order: "ORD-1".to_string(),
+        }
     }
 
-    // Truncar la lista de asientos disponibles al número deseado
-    available_seats.truncate(seats_amount as usize);
-    available_seats
-}
-
-/// Función para encontrar asientos adicionales en filas adyacentes dentro de una sección.
-fn find_additional_seats_in_section(
-    seats_needed: usize,
-    section: Section,
-    seats_guard: &HashMap<(Section, u32, u32), Seat>,
-    existing_seats: &Vec<(Section, u32, u32)>,
-) -> Vec<(Section, u32, u32)> {
-    let mut additional_seats = Vec::new();
-
-    // Obtener todas las filas y números en la sección
-    let mut seats_list: Vec<(u32, u32)> = seats_guard.keys()
-        .filter(|&&(sec, _, _)| sec == section)
-        .map(|&(_, row, number)| (row, number))
-        .collect();
-
-    // Ordenar los asientos por fila y número
-    seats_list.sort();
-
-    for &(row, number) in &seats_list {
-        if let Some(seat) = seats_guard.get(&(section, row, number)) {
-            if seat.booked == 'F' {
-                let seat_tuple = (section, row, number);
-                if !existing_seats.contains(&seat_tuple) && !additional_seats.contains(&seat_tuple) {
-                    additional_seats.push(seat_tuple);
-                    if additional_seats.len() >= seats_needed {
-                        break;
-                    }
-                }
+    #[test]
+    fn transition_table() {
+        let all = [SeatStatus::Free, held(1), booked(), blocked(), buffer()];
+        let legal = [
+            ('F', 'R'),
+            ('F', 'X'),
+            ('F', 'D'),
+            ('R', 'F'),
+            ('R', 'R'),
+            ('R', 'B'),
+            ('X', 'F'),
+            ('D', 'F'),
+        ];
+        for from in &all {
+            for to in &all {
+                let expected = legal.contains(&(from.code(), to.code()));
+                assert_eq!(
+                    from.transition(to).is_ok(),
+                    expected,
+                    "{} -> {}",
+                    from.code(),
+                    to.code()
+                );
             }
         }
     }
 
-    additional_seats
-}
+    #[test]
+    fn rejected_transition_reports_both_states() {
+        assert_eq!(
+            booked().transition(&SeatStatus::Free),
+            Err(IllegalTransition { from: 'B', to: 'F' })
+        );
+        assert_eq!(
+            SeatStatus::Free.transition(&booked()),
+            Err(IllegalTransition { from: 'F', to: 'B' })
+        );
+    }
 
-/// Función para marcar un asiento con un estado específico.
-pub fn mark_seat_as(
-    state: char,
-    seats: Arc<Mutex<HashMap<(Section, u32, u32), Seat>>>,
-    section: Section,
-    row: u32,
-    number: u32,
-) {
-    let mut seats_guard = seats.lock().unwrap();
-    if let Some(seat) = seats_guard.get_mut(&(section, row, number)) {
-        seat.booked = state;
+    fn hold_apart(seats: &SeatMap, number: u32, distancing: Distancing) -> Result<(), SeatError> {
+        let keys = [("F".to_string(), 1, number)];
+        transition_seats_apart(
+            seats,
+            &keys,
+            SeatStatus::is_free,
+            distancing,
+            &held(2),
+            || Ok(()),
+        )
     }
-}
 
-/// Función para obtener el estado actual de todos los asientos
-pub fn get_seat_states(
-    seats: Arc<Mutex<HashMap<(Section, u32, u32), Seat>>>,
-) -> Vec<SeatState> {
-    let seats_guard = seats.lock().unwrap();
-    let mut seat_states = Vec::new();
+    #[test]
+    fn hold_next_to_another_group_is_rejected_with_distancing() {
+        let seats = row_of_seats(&[held(1), SeatStatus::Free, SeatStatus::Free]);
+        let distancing = Distancing { seats: 1, rows: 0 };
+        assert_eq!(
+            hold_apart(&seats, 2, distancing),
+            Err(SeatError::Crowded(vec![("F".to_string(), 1, 2)]))
+        );
+        assert_eq!(
+            seats.lock().unwrap()[&("F".to_string(), 1, 2)].status,
+            SeatStatus::Free
+        );
+        assert!(hold_apart(&seats, 3, distancing).is_ok());
+        assert!(hold_apart(&seats, 2, Distancing::default()).is_ok());
+    }
 
-    for ((section, row, number), seat) in seats_guard.iter() {
-        seat_states.push(SeatState {
-            section: *section,
-            row: *row,
-            number: *number,
-            booked: seat.booked,
+    #[test]
+    fn failed_persist_leaves_the_seats_unchanged() {
+        let seats = row_of_seats(&[SeatStatus::Free, SeatStatus::Free]);
+        let keys = [("F".to_string(), 1, 1), ("F".to_string(), 1, 2)];
+        let result = transition_seats(&seats, &keys, SeatStatus::is_free, &held(1), || {
+            Err(StorageError::Corrupt("disco lleno".to_string()))
         });
+        assert!(matches!(result, Err(SeatError::Storage(_))));
+        let seats_guard = seats.lock().unwrap();
+        assert!(keys.iter().all(|key| seats_guard[key].status == SeatStatus::Free));
     }
 
-    seat_states
-}
\ No newline at end of file
+    #[test]
+    fn zone_stops_at_the_last_row_and_number() {
+        let distancing = Distancing { seats: 1, rows: 1 };
+        let corner = ("F".to_string(), u32::MAX, u32::MAX);
+        assert_eq!(distancing.zone(&corner).count(), 3);
+    }
+
+    #[test]
+    fn hold_renews_only_for_the_same_session() {
+        assert!(held(1).transition(&held(1)).is_ok());
+        assert_eq!(
+            held(1).transition(&held(2)),
+            Err(IllegalTransition { from: 'R', to: 'R' })
+        );
+    }
+}
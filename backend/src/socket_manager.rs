@@ -1,37 +1,848 @@
 // socket_manager.rs
 
-use crate::seat_manager::{find_seats_suggestions_by_category, get_seat_states, mark_seat_as, Seat, Category, Section};
-use futures_util::{SinkExt, StreamExt};
-use serde::{Deserialize, Serialize};
-use serde_json;
-use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
+use crate::protocol::{
+    ClientEnvelope, ClientMessage, ErrorCode, EventInfo, SeatInfo, SeatRef, SeatSuggestion,
+    ServerEnvelope, ServerMessage, VenueInfo,
+};
+use crate::allocator::{Allocator, Contiguity, SeatRequest, Suggestion, MAX_SUGGESTIONS};
+use crate::events::{Event, EventCatalog, EventStatus, PresaleCode};
+use crate::holds::{HoldExpired, SessionId};
+use crate::orders::{random_token, unix_now, OrderId, OrderRegistry};
+use crate::payment::{CardDetails, ChargeRequest, PaymentGateway};
+use crate::seat_manager::{get_seat_states, SeatError, SeatKey, SeatState};
+use crate::waiting_room::{Admission, Place, WaitingRoom};
+use futures_util::{Sink, SinkExt, StreamExt};
+use crate::venue::CategoryId;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 use tokio::net::TcpListener;
+use tokio::sync::broadcast::{self, error::{RecvError, TryRecvError}};
 use tokio_tungstenite::accept_async;
 use tungstenite::protocol::Message as TungsteniteMessage;
 
-#[derive(Debug, Deserialize)]
-struct SeatRequest {
-    category: Category,
-    seat_count: u32,
+/// Recursos compartidos por todas las sesiones del servidor.
+pub struct ServerState {
+    pub events: Arc<EventCatalog>,
+    pub waiting_room: Arc<WaitingRoom>,
+    pub orders: Arc<OrderRegistry>,
+    pub allocator: Arc<Allocator>,
+    pub payments: Arc<dyn PaymentGateway>,
+    /// Clave del personal para buscar órdenes por cliente; `None` desactiva esas búsquedas.
+    pub staff_key: Option<String>,
 }
 
-#[derive(Serialize, Debug)]
-struct SeatSuggestion {
-    suggestion_number: usize,
-    seats: Vec<SeatInfo>,
-    total_price: f32,
+/// Contador para asignar un identificador único a cada sesión.
+static NEXT_SESSION_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Estado de la conversación con un cliente conectado.
+struct Session {
+    id: SessionId,
+    events: Arc<EventCatalog>,
+    /// Evento en el que el cliente está comprando; sus asientos se publican a la sesión.
+    event: Arc<Event>,
+    /// Lugar de la sesión en la sala de espera; solo compra una vez admitida.
+    place: Place,
+    orders: Arc<OrderRegistry>,
+    allocator: Arc<Allocator>,
+    payments: Arc<dyn PaymentGateway>,
+    staff_key: Option<String>,
+    /// Órdenes pagadas en esta sesión, que puede consultar sin su clave de acceso.
+    paid_orders: Vec<OrderId>,
+    /// Sugerencias enviadas que aún no fueron aceptadas ni rechazadas. No reservan asientos:
+    /// solo la que el cliente acepta se reserva temporalmente.
+    suggestions: Vec<Suggestion>,
+    /// Asientos marcados como 'R' por este cliente: los de la sugerencia aceptada o los
+    /// elegidos por el cliente, pendientes de pago.
+    accepted_seats: Vec<SeatKey>,
+    /// Identidad de la conexión para los topes por sesión si no pasó por la sala de espera.
+    connection_key: String,
 }
 
-#[derive(Serialize, Debug)]
-struct SeatInfo {
-    section: Section,
-    row: u32,
-    number: u32,
-    price: f32,
+impl Session {
+    fn new(state: &ServerState) -> Self {
+        Session {
+            id: NEXT_SESSION_ID.fetch_add(1, Ordering::Relaxed),
+            events: Arc::clone(&state.events),
+            event: Arc::clone(state.events.first()),
+            place: state.waiting_room.join(),
+            orders: Arc::clone(&state.orders),
+            allocator: Arc::clone(&state.allocator),
+            payments: Arc::clone(&state.payments),
+            staff_key: state.staff_key.clone(),
+            paid_orders: Vec::new(),
+            suggestions: Vec::new(),
+            accepted_seats: Vec::new(),
+            connection_key: random_token(),
+        }
+    }
+
+    /// Identidad del comprador para los topes por sesión: el token de admisión, que sigue
+    /// siendo el mismo al reconectarse, o la conexión si la sala de espera está desactivada.
+    fn buyer(&self) -> &str {
+        match self.place.admission() {
+            Admission::Admitted(admission) => &admission.token,
+            _ => &self.connection_key,
+        }
+    }
+
+    /// Indica si la sesión ya salió de la sala de espera.
+    fn is_admitted(&self) -> bool {
+        self.place.is_admitted()
+    }
+
+    /// Mensajes enviados al cliente apenas se conecta: su lugar en la fila, o lo que
+    /// reciben las sesiones admitidas.
+    fn welcome(&self) -> Vec<ServerMessage> {
+        match self.place.admission() {
+            Admission::Open => self.greeting(),
+            Admission::Waiting(_) => self.queue_position().into_iter().collect(),
+            Admission::Admitted(admission) => {
+                let mut messages = vec![ServerMessage::from(admission.clone())];
+                messages.extend(self.greeting());
+                messages
+            }
+        }
+    }
+
+    /// Mensajes enviados al cliente cuando puede empezar a comprar.
+    fn greeting(&self) -> Vec<ServerMessage> {
+        vec![
+            ServerMessage::Events {
+                events: self
+                    .events
+                    .all()
+                    .iter()
+                    .map(|event| EventInfo::from(event.as_ref()))
+                    .collect(),
+            },
+            ServerMessage::Venue(VenueInfo::from(self.event.venue.as_ref())),
+            self.seat_states(),
+        ]
+    }
+
+    /// Pasa la sesión al evento en el que pide o reserva asientos, si es otro evento
+    /// existente. Las reservas del evento anterior se liberan. Retorna el nuevo evento para
+    /// que la conexión se suscriba a sus cambios antes de atender el mensaje.
+    /// `get_state` no cambia el evento: solo consulta el que indica.
+    fn switch_event(&mut self, message: &ClientMessage) -> Option<Arc<Event>> {
+        if !self.is_admitted() {
+            return None;
+        }
+        let id = match message {
+            ClientMessage::RequestSeats(_) | ClientMessage::HoldSeats { .. } => message.event()?,
+            _ => return None,
+        };
+        if id == self.event.id {
+            return None;
+        }
+        let event = Arc::clone(self.events.get(id)?);
+        self.release_all();
+        println!("La sesión {} pasa al evento {}", self.id, event.id);
+        self.event = Arc::clone(&event);
+        Some(event)
+    }
+
+    /// Posición en la fila de la sesión, o `None` si no espera.
+    fn queue_position(&self) -> Option<ServerMessage> {
+        let (position, eta_secs) = self.place.position()?;
+        Some(ServerMessage::QueuePosition { position, eta_secs })
+    }
+
+    /// Revisa si le tocó el turno a la sesión tras una admisión de la sala de espera.
+    /// Retorna la admisión o la nueva posición, o `None` si la sesión no esperaba.
+    fn check_turn(&mut self) -> Option<ServerMessage> {
+        match self.place.try_admit() {
+            Some(admission) => {
+                println!("La sesión {} sale de la fila de espera", self.id);
+                Some(admission.into())
+            }
+            None => self.queue_position(),
+        }
+    }
+
+    /// Saca a la sesión de la fila con un token de admisión emitido antes.
+    fn present_token(&mut self, token: &str) -> ServerMessage {
+        if self.is_admitted() {
+            return ServerMessage::error(
+                ErrorCode::InvalidAdmissionToken,
+                "La sesión ya fue admitida",
+            );
+        }
+        match self.place.redeem(token) {
+            Some(admission) => {
+                println!("La sesión {} entra con un token de admisión", self.id);
+                admission.into()
+            }
+            None => ServerMessage::error(
+                ErrorCode::InvalidAdmissionToken,
+                "El token de admisión no es válido, venció o ya está en uso",
+            ),
+        }
+    }
+
+    /// Procesa un mensaje del cliente y retorna la respuesta a enviar.
+    async fn handle(&mut self, message: ClientMessage) -> ServerMessage {
+        // Mientras espera en la fila solo puede presentar un token o consultar sus órdenes
+        if !self.is_admitted()
+            && !matches!(
+                message,
+                ClientMessage::PresentToken { .. }
+                    | ClientMessage::GetOrder { .. }
+                    | ClientMessage::FindOrders { .. }
+            )
+        {
+            return ServerMessage::error(
+                ErrorCode::NotAdmitted,
+                "La sesión todavía espera su turno en la fila",
+            );
+        }
+
+        // `switch_event` ya pasó la sesión al evento de los pedidos y reservas; `get_state`
+        // puede consultar otro evento sin cambiar el de la sesión
+        if let Some(id) = message.event() {
+            if id != self.event.id {
+                return match self.events.get(id) {
+                    Some(event) if matches!(message, ClientMessage::GetState { .. }) => {
+                        event_seat_states(event)
+                    }
+                    _ => ServerMessage::error(
+                        ErrorCode::UnknownEvent,
+                        format!("El evento {} no existe", id),
+                    ),
+                };
+            }
+        }
+
+        // Solo se piden y reservan asientos con la venta abierta
+        let presale = if matches!(
+            message,
+            ClientMessage::RequestSeats(_) | ClientMessage::HoldSeats { .. }
+        ) {
+            match self.sales_access(message.access_code()) {
+                Ok(presale) => presale,
+                Err((code, message)) => return ServerMessage::error(code, message),
+            }
+        } else {
+            None
+        };
+
+        match message {
+            ClientMessage::RequestSeats(query) => {
+                let mut request = SeatRequest::from(query);
+                request.presale = presale;
+                self.request_seats(request)
+            }
+            ClientMessage::ChooseSuggestion { suggestion_number } => {
+                self.choose_suggestion(suggestion_number)
+            }
+            ClientMessage::RejectSuggestions => self.reject_suggestions(),
+            ClientMessage::HoldSeats {
+                seats,
+                wheelchair_spaces,
+                ..
+            } => self.hold_seats(seats, wheelchair_spaces, presale.as_ref()),
+            ClientMessage::Pay { card, customer } => self.pay(card, customer).await,
+            ClientMessage::GetOrder {
+                order_id,
+                access_key,
+            } => self.get_order(&order_id, access_key.as_deref()),
+            ClientMessage::FindOrders {
+                customer,
+                staff_key,
+            } => self.find_orders(&customer, &staff_key),
+            ClientMessage::PresentToken { token } => self.present_token(&token),
+            ClientMessage::GetState { .. } => self.seat_states(),
+        }
+    }
+
+    /// Verifica que el evento venda asientos en este momento. Durante la preventa se
+    /// necesita un código válido, que se retorna para limitar los asientos a los que habilita.
+    fn sales_access(
+        &self,
+        access_code: Option<&str>,
+    ) -> Result<Option<PresaleCode>, (ErrorCode, String)> {
+        match self.event.status() {
+            EventStatus::OnSale => Ok(None),
+            EventStatus::Presale => {
+                let Some(code) = access_code else {
+                    return Err((
+                        ErrorCode::InvalidAccessCode,
+                        format!(
+                            "El evento {} está en preventa: se necesita un código",
+                            self.event.name
+                        ),
+                    ));
+                };
+                match self.event.presale_code(code) {
+                    Some(presale) => Ok(Some(presale.clone())),
+                    None => Err((
+                        ErrorCode::InvalidAccessCode,
+                        format!("El código de preventa {} no es válido", code),
+                    )),
+                }
+            }
+            status => Err((
+                ErrorCode::NotOnSale,
+                format!("El evento {} está {}", self.event.name, status),
+            )),
+        }
+    }
+
+    fn seat_states(&self) -> ServerMessage {
+        event_seat_states(&self.event)
+    }
+
+    fn request_seats(&mut self, request: SeatRequest) -> ServerMessage {
+        if self.event.venue.category(&request.category).is_none() {
+            return ServerMessage::error(
+                ErrorCode::UnknownCategory,
+                format!("La categoría {} no existe", request.category),
+            );
+        }
+        if let Some(presale) = &request.presale {
+            let unlocked =
+                self.event.venue.sections.iter().any(|section| {
+                    section.category == request.category && presale.unlocks(section)
+                });
+            if !unlocked {
+                return ServerMessage::error(
+                    ErrorCode::NotUnlocked,
+                    format!(
+                        "El código de preventa no habilita la categoría {}",
+                        request.category
+                    ),
+                );
+            }
+        }
+        if request.seats_amount == 0 {
+            return ServerMessage::error(
+                ErrorCode::InvalidSeatCount,
+                "Debe solicitar al menos un asiento",
+            );
+        }
+        // Un pedido mayor que la categoría entera no puede atenderse; se rechaza antes de
+        // consultar los topes o buscar asientos
+        let capacity: u32 = self
+            .event
+            .venue
+            .sections
+            .iter()
+            .filter(|section| section.category == request.category)
+            .flat_map(|section| &section.rows)
+            .sum();
+        if request.seats_amount > capacity {
+            return ServerMessage::error(
+                ErrorCode::InvalidSeatCount,
+                format!(
+                    "La categoría {} tiene {} asientos en total",
+                    request.category, capacity
+                ),
+            );
+        }
+        if request.wheelchair_spaces > request.seats_amount {
+            return ServerMessage::error(
+                ErrorCode::InvalidSeatCount,
+                "Los espacios para silla de ruedas no pueden superar los asientos pedidos",
+            );
+        }
+        if request.max_suggestions == 0 || request.max_suggestions > MAX_SUGGESTIONS {
+            return ServerMessage::error(
+                ErrorCode::InvalidSuggestionCount,
+                format!("Se pueden pedir entre 1 y {} sugerencias", MAX_SUGGESTIONS),
+            );
+        }
+        if let Some(limit) = request.max_total_price {
+            if !limit.is_finite() || limit <= 0.0 {
+                return ServerMessage::error(
+                    ErrorCode::InvalidPriceLimit,
+                    "El precio máximo debe ser mayor que cero",
+                );
+            }
+        }
+        let unknown_section = request
+            .preferred_sections
+            .iter()
+            .chain(&request.excluded_sections)
+            .find(|section| self.event.venue.section(section).is_none());
+        if let Some(section) = unknown_section {
+            return ServerMessage::error(
+                ErrorCode::UnknownSection,
+                format!("La sección {} no existe", section),
+            );
+        }
+
+        if let Err((code, message)) = self.check_limits(&request.category, request.seats_amount) {
+            return ServerMessage::error(code, message);
+        }
+
+        // Obtener las sugerencias de asientos, sin las de categorías cuyos topes no alcanzan.
+        // No se reservan: otro cliente puede tomar sus asientos antes de que se acepte una
+        let mut found = self.allocator.find_seats_suggestions_by_category(
+            &request,
+            &self.event.venue,
+            &self.event.seats,
+        );
+        found.retain(|suggestion| {
+            self.check_limits(&suggestion.category, request.seats_amount)
+                .is_ok()
+        });
+        println!("Sugerencias encontradas: {:?}", found);
+        self.suggestions = found;
+
+        // Formatear las sugerencias para enviarlas al cliente; las mejoras se comparan con
+        // la primera sugerencia de la categoría pedida
+        let mut baseline = None;
+        let suggestions = self
+            .suggestions
+            .iter()
+            .enumerate()
+            .map(|(index, suggestion)| {
+                let (seats, total_price) = self.priced_seats(&suggestion.seats);
+                let price_difference = if suggestion.upgrade {
+                    baseline.map(|baseline| total_price - baseline)
+                } else {
+                    baseline.get_or_insert(total_price);
+                    None
+                };
+                SeatSuggestion {
+                    suggestion_number: index + 1,
+                    seats,
+                    total_price,
+                    contiguity: Contiguity::of(&suggestion.seats),
+                    category: suggestion.category.clone(),
+                    upgrade: suggestion.upgrade,
+                    price_difference,
+                }
+            })
+            .collect();
+
+        ServerMessage::Suggestions { suggestions }
+    }
+
+    /// Reserva temporalmente los asientos elegidos por el cliente y los deja listos para pagar.
+    /// Las sugerencias pendientes y los asientos aceptados antes se liberan primero.
+    /// Como en las sugerencias, los espacios para silla de ruedas y de acompañantes quedan
+    /// reservados a quien indica `wheelchair_spaces`, y no se toman más espacios que esos.
+    fn hold_seats(
+        &mut self,
+        seats: Vec<SeatRef>,
+        wheelchair_spaces: u32,
+        presale: Option<&PresaleCode>,
+    ) -> ServerMessage {
+        let mut keys: Vec<SeatKey> = Vec::with_capacity(seats.len());
+        for seat in seats {
+            let key = seat.key();
+            if keys.contains(&key) {
+                return ServerMessage::error(
+                    ErrorCode::InvalidSeats,
+                    format!("El asiento {}-{}-{} está repetido", key.0, key.1, key.2),
+                );
+            }
+            keys.push(key);
+        }
+        if keys.is_empty() {
+            return ServerMessage::error(
+                ErrorCode::InvalidSeats,
+                "Debe indicar al menos un asiento",
+            );
+        }
+        if wheelchair_spaces as usize > keys.len() {
+            return ServerMessage::error(
+                ErrorCode::InvalidSeatCount,
+                "Los espacios para silla de ruedas no pueden superar los asientos elegidos",
+            );
+        }
+        let mut wheelchairs = 0;
+        for (section, row, number) in &keys {
+            // Las secciones desconocidas se informan al reservar
+            let Some(attributes) = self
+                .event
+                .venue
+                .section(section)
+                .map(|section| section.seat_attributes(*row, *number))
+            else {
+                continue;
+            };
+            if attributes.is_accessible() && wheelchair_spaces == 0 {
+                return ServerMessage::error(
+                    ErrorCode::AccessibleSeat,
+                    format!(
+                        "El asiento {}-{}-{} está reservado para personas en silla de ruedas y sus acompañantes",
+                        section, row, number
+                    ),
+                );
+            }
+            if attributes.wheelchair {
+                wheelchairs += 1;
+            }
+        }
+        if wheelchairs > wheelchair_spaces {
+            return ServerMessage::error(
+                ErrorCode::AccessibleSeat,
+                format!(
+                    "Se eligieron {} espacios para silla de ruedas y se indicaron {}",
+                    wheelchairs, wheelchair_spaces
+                ),
+            );
+        }
+        if let Some(presale) = presale {
+            // Los asientos de secciones desconocidas se rechazan al reservar
+            let locked = keys.iter().find(|(section, _, _)| {
+                self.event
+                    .venue
+                    .section(section)
+                    .is_some_and(|section| !presale.unlocks(section))
+            });
+            if let Some((section, row, number)) = locked {
+                return ServerMessage::error(
+                    ErrorCode::NotUnlocked,
+                    format!(
+                        "El código de preventa no habilita el asiento {}-{}-{}",
+                        section, row, number
+                    ),
+                );
+            }
+        }
+
+        for (category, count) in self.seats_by_category(&keys) {
+            if let Err((code, message)) = self.check_limits(&category, count) {
+                return ServerMessage::error(code, message);
+            }
+        }
+
+        self.suggestions.clear();
+        self.release_accepted();
+
+        if let Err(e) = self.event.holds.hold_seats(self.id, &keys) {
+            return hold_error(e);
+        }
+
+        let (seats, total_price) = self.priced_seats(&keys);
+        self.accepted_seats = keys;
+
+        println!("El cliente ha elegido {} asientos", seats.len());
+        ServerMessage::SeatsHeld { seats, total_price }
+    }
+
+    /// Cantidad de asientos de cada categoría entre los indicados.
+    fn seats_by_category(&self, seats: &[SeatKey]) -> BTreeMap<CategoryId, u32> {
+        let mut counts = BTreeMap::new();
+        for (section, _, _) in seats {
+            if let Some(section) = self.event.venue.section(section) {
+                *counts.entry(section.category.clone()).or_insert(0) += 1;
+            }
+        }
+        counts
+    }
+
+    /// Verifica que comprar `seats` asientos de la categoría respete sus topes por orden y
+    /// por sesión en el evento actual. Al pagar se vuelven a verificar, junto con el tope por
+    /// cliente, en la misma transacción que guarda la orden.
+    fn check_limits(&self, category: &str, seats: u32) -> Result<(), (ErrorCode, String)> {
+        let Some(category) = self.event.venue.category(category) else {
+            return Ok(());
+        };
+        let limits = category.limits;
+
+        if let Some(limit) = limits.per_order {
+            if seats > limit {
+                return Err((
+                    ErrorCode::PurchaseLimit,
+                    format!(
+                        "Se pueden comprar hasta {} asientos {} por orden",
+                        limit, category.name
+                    ),
+                ));
+            }
+        }
+
+        if let Some(limit) = limits.per_session {
+            let bought = self
+                .orders
+                .purchased_by(&self.event.id, &category.id, self.buyer())
+                .map_err(|e| {
+                    eprintln!(
+                        "No se pudieron consultar las compras de la sesión {}: {}",
+                        self.id, e
+                    );
+                    (
+                        ErrorCode::Internal,
+                        "No se pudieron consultar las compras de la sesión".to_string(),
+                    )
+                })?;
+            if bought.saturating_add(seats) > limit {
+                return Err((
+                    ErrorCode::PurchaseLimit,
+                    format!(
+                        "Se pueden comprar hasta {} asientos {} por sesión en cada evento y ya se compraron {}",
+                        limit, category.name, bought
+                    ),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Asientos con el precio de cada uno y el total.
+    fn priced_seats(&self, keys: &[SeatKey]) -> (Vec<SeatInfo>, f32) {
+        let seats_guard = self.event.seats.lock().unwrap();
+        let seats: Vec<SeatInfo> = keys
+            .iter()
+            .filter_map(|seat_key| {
+                let (section, row, number) = seat_key;
+                seats_guard.get(seat_key).map(|seat| SeatInfo {
+                    section: section.clone(),
+                    row: *row,
+                    number: *number,
+                    price: seat.price,
+                    attributes: seat.attributes,
+                })
+            })
+            .collect();
+        let total_price = seats.iter().map(|seat| seat.price).sum();
+        (seats, total_price)
+    }
+
+    fn choose_suggestion(&mut self, suggestion_number: usize) -> ServerMessage {
+        if self.suggestions.is_empty() {
+            return ServerMessage::error(
+                ErrorCode::NoPendingSuggestions,
+                "No hay sugerencias pendientes",
+            );
+        }
+        if suggestion_number < 1 || suggestion_number > self.suggestions.len() {
+            return ServerMessage::error(
+                ErrorCode::InvalidSuggestion,
+                format!("La sugerencia {} no existe", suggestion_number),
+            );
+        }
+
+        // La venta pudo cerrarse o pausarse desde que se pidieron las sugerencias
+        let status = self.event.status();
+        if !status.is_open() {
+            return ServerMessage::error(
+                ErrorCode::NotOnSale,
+                format!("El evento {} está {}", self.event.name, status),
+            );
+        }
+
+        // Reservar temporalmente ('R') la sugerencia aceptada de forma atómica; si otro
+        // cliente tomó alguno de sus asientos, las sugerencias siguen pendientes para elegir otra
+        let suggestion = &self.suggestions[suggestion_number - 1];
+        let accepted = suggestion.seats.clone();
+        if let Err((code, message)) = self.check_limits(&suggestion.category, accepted.len() as u32)
+        {
+            return ServerMessage::error(code, message);
+        }
+        self.release_accepted();
+        if let Err(e) = self.event.holds.hold_seats(self.id, &accepted) {
+            println!("Sugerencia {} no disponible: {}", suggestion_number, e);
+            return hold_error(e);
+        }
+        self.suggestions.clear();
+        self.accepted_seats = accepted;
+
+        println!("El cliente ha aceptado la sugerencia {}", suggestion_number);
+        ServerMessage::SuggestionAccepted { suggestion_number }
+    }
+
+    fn reject_suggestions(&mut self) -> ServerMessage {
+        if self.suggestions.is_empty() {
+            return ServerMessage::error(
+                ErrorCode::NoPendingSuggestions,
+                "No hay sugerencias pendientes",
+            );
+        }
+
+        self.suggestions.clear();
+
+        println!("El cliente ha rechazado todas las sugerencias");
+        ServerMessage::SuggestionsRejected
+    }
+
+    /// Cobra los asientos aceptados por esta sesión y, si el cobro se aprueba, los reserva ('B').
+    async fn pay(&mut self, card: CardDetails, customer: Option<String>) -> ServerMessage {
+        if self.accepted_seats.is_empty() {
+            return ServerMessage::error(
+                ErrorCode::NothingToPay,
+                "No hay asientos aceptados pendientes de pago",
+            );
+        }
+        if !self.event.holds.holds_all(self.id, &self.accepted_seats) {
+            self.release_accepted();
+            return ServerMessage::error(
+                ErrorCode::HoldLost,
+                "La reserva temporal de los asientos ya no está vigente",
+            );
+        }
+
+        let customer = customer
+            .filter(|customer| !customer.trim().is_empty())
+            .unwrap_or_else(|| card.holder.clone());
+        for (category, count) in self.seats_by_category(&self.accepted_seats) {
+            if let Err((code, message)) = self.check_limits(&category, count) {
+                self.release_accepted();
+                return ServerMessage::error(code, message);
+            }
+        }
+
+        // Renovar la reserva mientras se procesa el cobro
+        self.event.holds.renew(self.id, &self.accepted_seats);
+
+        // El monto se calcula en el servidor a partir del precio de cada asiento
+        let created_at = unix_now();
+        let lines: Vec<(SeatKey, CategoryId, f32)> = {
+            let seats_guard = self.event.seats.lock().unwrap();
+            self.accepted_seats
+                .iter()
+                .filter_map(|seat_key| {
+                    let seat = seats_guard.get(seat_key)?;
+                    let section = self.event.venue.section(&seat.section)?;
+                    Some((seat_key.clone(), section.category.clone(), seat.price))
+                })
+                .collect()
+        };
+        let total_price: f32 = lines.iter().map(|(_, _, price)| price).sum();
+
+        let request = ChargeRequest {
+            reference: format!("sesion-{}", self.id),
+            amount: total_price,
+            card,
+        };
+
+        match self.payments.charge(request).await {
+            Ok(receipt) => {
+                let order = self.orders.new_order(
+                    &self.event.id,
+                    &customer,
+                    self.buyer(),
+                    &lines,
+                    &receipt,
+                    created_at,
+                );
+                let booked =
+                    self.event
+                        .holds
+                        .book(self.id, &self.accepted_seats, &order, &self.event.venue);
+                if let Err(conflict) = booked {
+                    println!("No se pudieron reservar los asientos pagados: {}", conflict);
+                    // La reserva se perdió o se superó un tope durante el cobro: devolver el dinero
+                    if let Err(e) = self.payments.refund(&receipt).await {
+                        eprintln!("Error al reembolsar {}: {}", receipt.transaction_id, e);
+                    }
+                    self.release_accepted();
+                    return match conflict {
+                        SeatError::Limit(message) => ServerMessage::error(
+                            ErrorCode::PurchaseLimit,
+                            format!("{}; el cobro fue reembolsado", message),
+                        ),
+                        SeatError::Storage(_) => ServerMessage::error(
+                            ErrorCode::Internal,
+                            "No se pudo guardar la compra; el cobro fue reembolsado",
+                        ),
+                        _ => ServerMessage::error(
+                            ErrorCode::HoldLost,
+                            "La reserva temporal venció durante el pago; el cobro fue reembolsado",
+                        ),
+                    };
+                }
+
+                let booked = std::mem::take(&mut self.accepted_seats);
+                self.paid_orders.push(order.id.clone());
+
+                ServerMessage::PaymentAccepted {
+                    seats: booked.iter().map(SeatRef::from).collect(),
+                    total_price,
+                    transaction_id: receipt.transaction_id,
+                    order,
+                }
+            }
+            Err(e) => {
+                println!("Pago rechazado para la sesión {}: {}", self.id, e);
+                self.release_accepted();
+                ServerMessage::PaymentDeclined {
+                    reason: e.to_string(),
+                }
+            }
+        }
+    }
+
+    /// Consulta una orden pagada en esta sesión, o la de otra sesión con su clave de acceso.
+    /// Una orden ajena sin la clave correcta se informa como inexistente.
+    fn get_order(&self, order_id: &str, access_key: Option<&str>) -> ServerMessage {
+        let unknown = || {
+            ServerMessage::error(
+                ErrorCode::UnknownOrder,
+                format!("La orden {} no existe", order_id),
+            )
+        };
+        match self.orders.find(order_id) {
+            Ok(Some(order)) => {
+                let allowed = self.paid_orders.contains(&order.id)
+                    || access_key.is_some_and(|key| same_secret(key, &order.access_key));
+                if allowed {
+                    ServerMessage::Order { order }
+                } else {
+                    unknown()
+                }
+            }
+            Ok(None) => unknown(),
+            Err(e) => {
+                eprintln!("Error al consultar la orden {}: {}", order_id, e);
+                ServerMessage::error(ErrorCode::Internal, "No se pudo consultar la orden")
+            }
+        }
+    }
+
+    /// Consulta todas las órdenes de un cliente, solo con la clave del personal.
+    fn find_orders(&self, customer: &str, staff_key: &str) -> ServerMessage {
+        let authorized = self
+            .staff_key
+            .as_deref()
+            .is_some_and(|key| same_secret(staff_key, key));
+        if !authorized {
+            println!(
+                "Búsqueda de órdenes por cliente rechazada en la sesión {}",
+                self.id
+            );
+            return ServerMessage::error(
+                ErrorCode::Forbidden,
+                "Solo el personal puede buscar órdenes por cliente",
+            );
+        }
+        match self.orders.find_by_customer(customer) {
+            Ok(orders) => ServerMessage::Orders { orders },
+            Err(e) => {
+                eprintln!("Error al consultar las órdenes de {}: {}", customer, e);
+                ServerMessage::error(ErrorCode::Internal, "No se pudieron consultar las órdenes")
+            }
+        }
+    }
+
+    /// Libera ('R' -> 'F') los asientos aceptados que aún no se pagaron.
+    fn release_accepted(&mut self) {
+        let accepted = std::mem::take(&mut self.accepted_seats);
+        self.event.holds.release(self.id, &accepted);
+    }
+
+    /// Olvida los asientos cuya reserva venció (ya fueron liberados por el recolector).
+    fn hold_expired(&mut self, expired: &[SeatKey]) -> ServerMessage {
+        self.accepted_seats.retain(|seat| !expired.contains(seat));
+
+        ServerMessage::HoldExpired {
+            seats: expired.iter().map(SeatRef::from).collect(),
+        }
+    }
+
+    /// Libera todos los asientos reservados por el cliente (al desconectarse o cambiar de evento).
+    fn release_all(&mut self) {
+        self.release_accepted();
+        self.suggestions.clear();
+    }
 }
 
-pub async fn start_socket_server(seats: Arc<Mutex<HashMap<(Section, u32, u32), Seat>>>) {
+pub async fn start_socket_server(state: Arc<ServerState>) {
     let addr = "127.0.0.1:8080";
     let listener = TcpListener::bind(&addr).await.expect("Failed to bind");
 
@@ -40,213 +851,156 @@ pub async fn start_socket_server(seats: Arc<Mutex<HashMap<(Section, u32, u32), S
     loop {
         match listener.accept().await {
             Ok((stream, _)) => {
-                let seats = Arc::clone(&seats);
+                let state = Arc::clone(&state);
                 tokio::spawn(async move {
-                    // Lista para almacenar los asientos reservados por este cliente
-                    let mut client_reserved_seats: Vec<(Section, u32, u32)> = Vec::new();
-                    let mut seat_suggestions: Vec<Vec<(Section, u32, u32)>> = Vec::new();
+                    // Entrar a la sala de espera solo si el handshake se completó
+                    let ws_stream = match accept_async(stream).await {
+                        Ok(ws_stream) => ws_stream,
+                        Err(e) => {
+                            eprintln!("Error durante el handshake WebSocket: {}", e);
+                            return;
+                        }
+                    };
 
-                    let ws_stream = accept_async(stream)
-                        .await
-                        .expect("Error durante el handshake WebSocket");
+                    // Suscribirse antes de tomar la foto inicial para no perder cambios
+                    let mut session = Session::new(&state);
+                    let mut updates = session.event.seats.subscribe();
+                    let mut expirations = session.event.holds.subscribe();
+                    let mut statuses = session.event.subscribe();
+                    let mut admissions = session.place.subscribe();
 
                     let (mut ws_sender, mut ws_receiver) = ws_stream.split();
 
-                    // Enviar el estado actual de los asientos al cliente al conectarse
-                    let seat_states = get_seat_states(seats.clone());
-                    let seat_states_json = serde_json::to_string(&seat_states).unwrap();
-
-                    if ws_sender
-                        .send(TungsteniteMessage::Text(seat_states_json))
-                        .await
-                        .is_err()
-                    {
+                    // Enviar el recinto y el estado actual de los asientos al cliente al conectarse,
+                    // o su lugar en la fila si la sala de espera está activa
+                    if !send_all(&mut ws_sender, session.welcome()).await {
                         eprintln!("Error al enviar el estado de los asientos al cliente");
                     }
 
-                    // Bucle para manejar los mensajes del cliente
-                    while let Some(message) = ws_receiver.next().await {
-                        match message {
-                            Ok(TungsteniteMessage::Text(request_str)) => {
-                                println!("Mensaje recibido: {}", request_str);
-
-                                // Intentar parsear como JSON
-                                let parsed_message: serde_json::Value = serde_json::from_str(&request_str).unwrap_or(serde_json::Value::Null);
-
-                                if parsed_message["type"] == "payment_result" {
-                                    // Manejar resultado del pago
-                                    let success = parsed_message["success"].as_bool().unwrap_or(false);
-                                    let seats_array_data = parsed_message["seats"].as_array().unwrap_or(&Vec::new()).clone();
-
-                                    let seats_to_update: Vec<(Section, u32, u32)> = seats_array_data.iter().filter_map(|seat_info| {
-                                        let section_str = seat_info["section"].as_str().unwrap_or("");
-                                        let section = match section_str {
-                                            "A1" => Section::A1,
-                                            "B1" => Section::B1,
-                                            "C1" => Section::C1,
-                                            "A2" => Section::A2,
-                                            "B2" => Section::B2,
-                                            "C2" => Section::C2,
-                                            "A3" => Section::A3,
-                                            "B3" => Section::B3,
-                                            "C3" => Section::C3,
-                                            "D" => Section::D,
-                                            "E" => Section::E,
-                                            "F" => Section::F,
-                                            _ => return None,
-                                        };
-                                        let row = seat_info["row"].as_u64().unwrap_or(0) as u32;
-                                        let number = seat_info["number"].as_u64().unwrap_or(0) as u32;
-                                        Some((section, row, number))
-                                    }).collect();
-
-                                    if success {
-                                        // Marcar asientos como 'B'
-                                        for (section, row, number) in &seats_to_update {
-                                            mark_seat_as('B', seats.clone(), *section, *row, *number);
-                                            // Remover los asientos de client_reserved_seats
-                                            client_reserved_seats.retain(|&seat| seat != (*section, *row, *number));
-                                        }
-                                        // Enviar estado actualizado de asientos
-                                        let seat_states = get_seat_states(seats.clone());
-                                        let seat_states_json = serde_json::to_string(&seat_states).unwrap();
-                                        if ws_sender.send(TungsteniteMessage::Text(seat_states_json)).await.is_err() {
-                                            eprintln!("Error al enviar el estado de los asientos al cliente");
-                                        }
-                                        // Enviar confirmación
-                                        if ws_sender.send(TungsteniteMessage::Text("Pago exitoso".to_string())).await.is_err() {
-                                            eprintln!("Error al enviar confirmación al cliente");
-                                        }
-                                    } else {
-                                        // Liberar asientos
-                                        for (section, row, number) in &seats_to_update {
-                                            mark_seat_as('F', seats.clone(), *section, *row, *number);
-                                            client_reserved_seats.retain(|&seat| seat != (*section, *row, *number));
-                                        }
-                                        // Enviar mensaje de error
-                                        if ws_sender.send(TungsteniteMessage::Text("Pago fallido. Intente nuevamente.".to_string())).await.is_err() {
-                                            eprintln!("Error al enviar mensaje al cliente");
-                                        }
-                                    }
-                                } else {
-                                    // Intentar parsear como SeatRequest
-                                    let seat_request: Result<SeatRequest, _> = serde_json::from_str(&request_str);
-
-                                    if let Ok(seat_request) = seat_request {
-                                        // Obtener las sugerencias de asientos
-                                        seat_suggestions = find_seats_suggestions_by_category(
-                                            seat_request.seat_count,
-                                            seat_request.category,
-                                            seats.clone(),
-                                        );
-
-                                        println!("Sugerencias encontradas: {:?}", seat_suggestions);
-
-                                        // Marcar los asientos sugeridos como reservados temporalmente ('R')
-                                        for suggestion in &seat_suggestions {
-                                            for &(section, row, number) in suggestion {
-                                                mark_seat_as('R', seats.clone(), section, row, number);
-
-                                                // Agregar el asiento a la lista de asientos reservados por el cliente
-                                                client_reserved_seats.push((section, row, number));
-                                            }
-                                        }
+                    // Bucle para manejar los mensajes del cliente y los cambios de otros clientes
+                    loop {
+                        tokio::select! {
+                            message = ws_receiver.next() => {
+                                let Some(message) = message else {
+                                    break;
+                                };
+                                match message {
+                                    Ok(TungsteniteMessage::Text(request_str)) => {
+                                        let was_admitted = session.is_admitted();
 
-                                        // Formatear las sugerencias para enviarlas al cliente
-                                        let formatted_suggestions: Vec<SeatSuggestion> = {
-                                            let seats_guard = seats.lock().unwrap();
-
-                                            seat_suggestions.iter().enumerate().map(|(index, seats_vec)| {
-                                                let mut total_price = 0.0;
-                                                let seat_infos: Vec<SeatInfo> = seats_vec.iter()
-                                                    .map(|(section, row, number)| {
-                                                        let seat = seats_guard.get(&(*section, *row, *number)).unwrap();
-                                                        total_price += seat.price;
-                                                        SeatInfo {
-                                                            section: *section,
-                                                            row: *row,
-                                                            number: *number,
-                                                            price: seat.price,
-                                                        }
-                                                    })
-                                                    .collect();
-
-                                                SeatSuggestion {
-                                                    suggestion_number: index + 1,
-                                                    seats: seat_infos,
-                                                    total_price,
+                                        let (request_id, response) =
+                                            match serde_json::from_str::<ClientEnvelope>(&request_str) {
+                                                Ok(envelope) => {
+                                                    // Solo se registra el tipo: el mensaje puede traer datos de pago o claves
+                                                    println!("Mensaje recibido de la sesión {}: {}", session.id, envelope.message.kind());
+                                                    // Al cambiar de evento, suscribirse a sus cambios antes de atender el mensaje
+                                                    if let Some(event) = session.switch_event(&envelope.message) {
+                                                        updates = event.seats.subscribe();
+                                                        expirations = event.holds.subscribe();
+                                                        statuses = event.subscribe();
+                                                    }
+                                                    (envelope.request_id, session.handle(envelope.message).await)
                                                 }
-                                            }).collect()
-                                        };
-
-                                        println!("Sugerencias formateadas: {:?}", formatted_suggestions);
+                                                Err(e) => (
+                                                    None,
+                                                    ServerMessage::error(
+                                                        ErrorCode::InvalidMessage,
+                                                        format!("Mensaje inválido: {}", e),
+                                                    ),
+                                                ),
+                                            };
 
-                                        // Enviar las sugerencias en formato JSON
-                                        let suggestions_json = serde_json::to_string(&formatted_suggestions).unwrap();
-                                        if ws_sender.send(TungsteniteMessage::Text(suggestions_json)).await.is_err() {
-                                            eprintln!("Error al enviar las sugerencias al cliente");
+                                        if ws_sender.send(to_text(request_id, response)).await.is_err() {
+                                            eprintln!("Error al enviar la respuesta al cliente");
                                             break; // Salir del bucle si hay un error al enviar
                                         }
-                                    } else {
-                                        // Manejo de la elección del cliente
-                                        let choice: usize = request_str.trim().parse().unwrap_or(0);
-
-                                        if choice >= 1 && choice <= seat_suggestions.len() {
-                                            // El cliente ha aceptado una de las sugerencias
-                                            let _accepted_seats = &seat_suggestions[choice - 1];
-
-                                            // Los asientos ya están marcados como 'R'
-
-                                            // Marcar las otras sugerencias como disponibles ('F')
-                                            for (i, suggestion) in seat_suggestions.iter().enumerate() {
-                                                if i != (choice - 1) {
-                                                    for &(section, row, number) in suggestion {
-                                                        mark_seat_as('F', seats.clone(), section, row, number);
-                                                        client_reserved_seats.retain(|&seat| seat != (section, row, number));
-                                                    }
-                                                }
-                                            }
-
-                                            println!("El cliente ha aceptado la sugerencia {}", choice);
-                                            // Enviar confirmación al cliente
-                                            if ws_sender.send(TungsteniteMessage::Text("Sugerencia aceptada".to_string())).await.is_err() {
-                                                eprintln!("Error al enviar la confirmación al cliente");
-                                                break; // Salir del bucle si hay un error al enviar
-                                            }
-                                        } else {
-                                            // El cliente ha rechazado todas las sugerencias
-                                            // Marcar todos los asientos sugeridos como disponibles ('F')
-                                            for suggestion in &seat_suggestions {
-                                                for &(section, row, number) in suggestion {
-                                                    mark_seat_as('F', seats.clone(), section, row, number);
-                                                    client_reserved_seats.retain(|&seat| seat != (section, row, number));
-                                                }
-                                            }
 
-                                            println!("El cliente ha rechazado todas las sugerencias");
-                                            // Enviar notificación al cliente
-                                            if ws_sender.send(TungsteniteMessage::Text("Sugerencias rechazadas".to_string())).await.is_err() {
-                                                eprintln!("Error al enviar la notificación al cliente");
+                                        // Al salir de la fila con un token, suscribirse y enviar el estado inicial
+                                        if !was_admitted && session.is_admitted() {
+                                            updates = session.event.seats.subscribe();
+                                            expirations = session.event.holds.subscribe();
+                                            statuses = session.event.subscribe();
+                                            if !send_all(&mut ws_sender, session.greeting()).await {
+                                                eprintln!("Error al enviar el estado de los asientos al cliente");
                                                 break;
                                             }
                                         }
+                                    },
+                                    Ok(_) => {
+                                        // Ignorar otros tipos de mensajes (Binary, Ping, Pong, etc.)
+                                    },
+                                    Err(e) => {
+                                        eprintln!("Error en la conexión WebSocket: {}", e);
+                                        break;
                                     }
                                 }
-                            },
-                            Ok(_) => {
-                                // Ignorar otros tipos de mensajes (Binary, Ping, Pong, etc.)
-                            },
-                            Err(e) => {
-                                eprintln!("Error en la conexión WebSocket: {}", e);
-                                break;
+                            }
+                            changed = admissions.changed(), if !session.is_admitted() => {
+                                if changed.is_err() {
+                                    break;
+                                }
+                                let Some(message) = session.check_turn() else {
+                                    continue;
+                                };
+                                let mut messages = vec![message];
+                                // Al ser admitida, suscribirse antes de tomar la foto inicial
+                                if session.is_admitted() {
+                                    updates = session.event.seats.subscribe();
+                                    expirations = session.event.holds.subscribe();
+                                    statuses = session.event.subscribe();
+                                    messages.extend(session.greeting());
+                                }
+                                if !send_all(&mut ws_sender, messages).await {
+                                    eprintln!("Error al avisar al cliente su lugar en la fila");
+                                    break;
+                                }
+                            }
+                            update = updates.recv(), if session.is_admitted() => {
+                                let message = match update {
+                                    Ok(first) => collect_updates(first, &mut updates, &session),
+                                    // El cliente se atrasó demasiado: enviarle el estado completo
+                                    Err(RecvError::Lagged(_)) => session.seat_states(),
+                                    Err(RecvError::Closed) => break,
+                                };
+                                if ws_sender.send(to_text(None, message)).await.is_err() {
+                                    eprintln!("Error al enviar los cambios de asientos al cliente");
+                                    break;
+                                }
+                            }
+                            expired = expirations.recv(), if session.is_admitted() => {
+                                let expired = match expired {
+                                    Ok(HoldExpired { session: owner, seats }) if owner == session.id => seats,
+                                    Ok(_) | Err(RecvError::Lagged(_)) => continue,
+                                    Err(RecvError::Closed) => break,
+                                };
+                                let message = session.hold_expired(&expired);
+                                if ws_sender.send(to_text(None, message)).await.is_err() {
+                                    eprintln!("Error al avisar al cliente del vencimiento de su reserva");
+                                    break;
+                                }
+                            }
+                            status = statuses.recv(), if session.is_admitted() => {
+                                let status = match status {
+                                    Ok(status) => status,
+                                    // Solo importa el último estado
+                                    Err(RecvError::Lagged(_)) => session.event.status(),
+                                    Err(RecvError::Closed) => break,
+                                };
+                                let message = ServerMessage::EventStatus {
+                                    event: session.event.id.clone(),
+                                    status,
+                                };
+                                if ws_sender.send(to_text(None, message)).await.is_err() {
+                                    eprintln!("Error al avisar al cliente del estado del evento");
+                                    break;
+                                }
                             }
                         }
                     }
 
-                    // Al finalizar la tarea (cliente desconectado), liberar los asientos reservados por este cliente
-                    for &(section, row, number) in &client_reserved_seats {
-                        mark_seat_as('F', seats.clone(), section, row, number);
-                    }
+                    // Al finalizar la tarea (cliente desconectado), liberar los asientos reservados por este cliente;
+                    // su lugar en la sala de espera se libera al soltar la sesión
+                    session.release_all();
                     println!("Asientos liberados para el cliente.");
 
                 });
@@ -257,3 +1011,71 @@ pub async fn start_socket_server(seats: Arc<Mutex<HashMap<(Section, u32, u32), S
         }
     }
 }
+
+/// Función auxiliar para armar el estado de todos los asientos de un evento.
+fn event_seat_states(event: &Event) -> ServerMessage {
+    ServerMessage::SeatStates {
+        event: event.id.clone(),
+        seats: get_seat_states(event.seats.clone()),
+    }
+}
+
+/// Respuesta de error para una reserva temporal que no pudo hacerse.
+fn hold_error(e: SeatError) -> ServerMessage {
+    let code = match e {
+        SeatError::UnknownSeats(_) => ErrorCode::UnknownSeats,
+        SeatError::Storage(_) => ErrorCode::Internal,
+        _ => ErrorCode::SeatsUnavailable,
+    };
+    ServerMessage::error(code, e.to_string())
+}
+
+/// Agrupa en un solo mensaje todos los cambios de asientos ya disponibles en el canal.
+fn collect_updates(
+    first: SeatState,
+    updates: &mut broadcast::Receiver<SeatState>,
+    session: &Session,
+) -> ServerMessage {
+    let mut changed = vec![first];
+    loop {
+        match updates.try_recv() {
+            Ok(seat) => changed.push(seat),
+            Err(TryRecvError::Lagged(_)) => return session.seat_states(),
+            Err(_) => break,
+        }
+    }
+    ServerMessage::SeatUpdates {
+        event: session.event.id.clone(),
+        seats: changed,
+    }
+}
+
+/// Envía varios mensajes sin identificador de solicitud; retorna `false` si alguno falló.
+async fn send_all<S>(sender: &mut S, messages: Vec<ServerMessage>) -> bool
+where
+    S: Sink<TungsteniteMessage> + Unpin,
+{
+    for message in messages {
+        if sender.send(to_text(None, message)).await.is_err() {
+            return false;
+        }
+    }
+    true
+}
+
+/// Compara una clave recibida con la esperada sin cortar en el primer carácter distinto,
+/// para que el tiempo de respuesta no revele cuánto de la clave se acertó.
+fn same_secret(received: &str, expected: &str) -> bool {
+    received.len() == expected.len()
+        && received
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// Serializa un mensaje del servidor como texto WebSocket.
+fn to_text(request_id: Option<String>, message: ServerMessage) -> TungsteniteMessage {
+    let envelope = ServerEnvelope { request_id, message };
+    TungsteniteMessage::Text(serde_json::to_string(&envelope).unwrap())
+}
@@ -0,0 +1,741 @@
+// storage.rs
+
+use crate::holds::SessionId;
+use crate::orders::{Order, OrderStatus, Ticket};
+use crate::seat_manager::{SeatKey, SeatMap, SeatStatus};
+use crate::venue::Venue;
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use std::fmt;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Esquema de la base de datos. Cada asiento de cada evento guarda su último estado
+/// conocido; las reservas temporales y las órdenes con sus entradas se guardan en tablas aparte.
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS seats (
+        event TEXT NOT NULL,
+        section TEXT NOT NULL,
+        row INTEGER NOT NULL,
+        number INTEGER NOT NULL,
+        state TEXT NOT NULL,
+        detail TEXT,
+        PRIMARY KEY (event, section, row, number)
+    );
+    CREATE TABLE IF NOT EXISTS holds (
+        event TEXT NOT NULL,
+        section TEXT NOT NULL,
+        row INTEGER NOT NULL,
+        number INTEGER NOT NULL,
+        session INTEGER NOT NULL,
+        expires_at INTEGER NOT NULL,
+        PRIMARY KEY (event, section, row, number)
+    );
+    CREATE TABLE IF NOT EXISTS orders (
+        number INTEGER PRIMARY KEY,
+        id TEXT NOT NULL UNIQUE,
+        access_key TEXT NOT NULL,
+        event TEXT NOT NULL,
+        customer TEXT NOT NULL,
+        buyer TEXT NOT NULL,
+        card_fingerprint TEXT NOT NULL,
+        session INTEGER NOT NULL,
+        transaction_id TEXT NOT NULL,
+        total_price REAL NOT NULL,
+        status TEXT NOT NULL,
+        created_at INTEGER NOT NULL,
+        paid_at INTEGER NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS orders_customer ON orders (customer);
+    CREATE INDEX IF NOT EXISTS orders_buyer ON orders (event, buyer);
+    CREATE INDEX IF NOT EXISTS orders_card ON orders (event, card_fingerprint);
+    CREATE TABLE IF NOT EXISTS tickets (
+        id TEXT PRIMARY KEY,
+        order_id TEXT NOT NULL REFERENCES orders (id),
+        section TEXT NOT NULL,
+        row INTEGER NOT NULL,
+        number INTEGER NOT NULL,
+        category TEXT NOT NULL,
+        price REAL NOT NULL
+    );
+";
+
+/// Errores posibles al leer o guardar el estado en la base de datos.
+#[derive(Debug)]
+pub enum StorageError {
+    Sqlite(rusqlite::Error),
+    /// La base de datos contiene un valor que el servidor no reconoce.
+    Corrupt(String),
+    /// Guardar la orden superaría un tope de compra; no se guardó nada.
+    Limit(String),
+}
+
+/// Quién compra, según el tope de compra que se verifica.
+#[derive(Debug, Clone, Copy)]
+pub enum Purchaser<'a> {
+    /// Identidad del comprador en el servidor (tope por sesión).
+    Buyer(&'a str),
+    /// Huella de la tarjeta con que se paga (tope por cliente).
+    Card(&'a str),
+}
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StorageError::Sqlite(e) => write!(f, "error de la base de datos: {}", e),
+            StorageError::Corrupt(msg) => write!(f, "base de datos inconsistente: {}", msg),
+            StorageError::Limit(msg) => f.write_str(msg),
+        }
+    }
+}
+
+impl std::error::Error for StorageError {}
+
+impl From<rusqlite::Error> for StorageError {
+    fn from(e: rusqlite::Error) -> Self {
+        StorageError::Sqlite(e)
+    }
+}
+
+/// Almacenamiento persistente de asientos, reservas temporales y órdenes en SQLite.
+///
+/// Cada operación se guarda en una sola transacción: o se aplica completa o no se aplica.
+pub struct Storage {
+    conn: Mutex<Connection>,
+}
+
+impl Storage {
+    /// Abre (o crea) la base de datos indicada y prepara su esquema por evento.
+    pub fn open(path: &Path) -> Result<Storage, StorageError> {
+        let conn = Connection::open(path)?;
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        conn.pragma_update(None, "synchronous", "NORMAL")?;
+        conn.execute_batch(SCHEMA)?;
+        Ok(Storage {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Abre una base de datos vacía en memoria, para las pruebas.
+    #[cfg(test)]
+    pub fn open_in_memory() -> Storage {
+        Storage::open(Path::new(":memory:")).unwrap()
+    }
+
+    /// Indica si la base de datos aún no tiene asientos guardados para el evento.
+    pub fn is_empty(&self, event: &str) -> Result<bool, StorageError> {
+        let conn = self.conn.lock().unwrap();
+        let count: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM seats WHERE event = ?1",
+            params![event],
+            |row| row.get(0),
+        )?;
+        Ok(count == 0)
+    }
+
+    /// Guarda el estado actual de todos los asientos del inventario del evento.
+    pub fn save_all(&self, event: &str, seats: &SeatMap) -> Result<(), StorageError> {
+        let seats_guard = seats.lock().unwrap();
+        self.with_transaction(|tx| {
+            for (key, seat) in seats_guard.iter() {
+                write_status(tx, event, key, &seat.status)?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Carga en el inventario del evento el estado guardado de cada asiento.
+    ///
+    /// Las reservas temporales no sobreviven a un reinicio porque sus sesiones ya no
+    /// existen: esos asientos vuelven a quedar libres. Los asientos del recinto que no
+    /// estaban guardados se agregan como libres.
+    pub fn restore(&self, event: &str, seats: &SeatMap) -> Result<(), StorageError> {
+        let mut seats_guard = seats.lock().unwrap();
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        {
+            let mut stmt = tx.prepare(
+                "SELECT section, row, number, state, detail FROM seats WHERE event = ?1",
+            )?;
+            let rows = stmt.query_map(params![event], |row| {
+                Ok((
+                    (
+                        row.get::<_, String>(0)?,
+                        row.get::<_, u32>(1)?,
+                        row.get::<_, u32>(2)?,
+                    ),
+                    row.get::<_, String>(3)?,
+                    row.get::<_, Option<String>>(4)?,
+                ))
+            })?;
+            for row in rows {
+                let (key, state, detail) = row?;
+                let status = match (state.as_str(), detail) {
+                    ("B", Some(order)) => SeatStatus::Booked { order },
+                    ("X", reason) => SeatStatus::Blocked {
+                        reason: reason.unwrap_or_default(),
+                    },
+                    ("D", order) => SeatStatus::Buffer {
+                        order: order.unwrap_or_default(),
+                    },
+                    ("F" | "R", _) => SeatStatus::Free,
+                    (other, _) => {
+                        return Err(StorageError::Corrupt(format!(
+                            "el asiento {}-{}-{} del evento {} tiene el estado {:?}",
+                            key.0, key.1, key.2, event, other
+                        )))
+                    }
+                };
+                match seats_guard.get_mut(&key) {
+                    Some(seat) => seat.status = status,
+                    None => println!(
+                        "El asiento guardado {}-{}-{} del evento {} ya no existe en el recinto",
+                        key.0, key.1, key.2, event
+                    ),
+                }
+            }
+        }
+
+        tx.execute("DELETE FROM holds WHERE event = ?1", params![event])?;
+        for (key, seat) in seats_guard.iter() {
+            write_status(&tx, event, key, &seat.status)?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Guarda la reserva temporal de los asientos del evento para la sesión.
+    pub fn hold_seats(
+        &self,
+        event: &str,
+        session: SessionId,
+        seats: &[SeatKey],
+        expires_at: SystemTime,
+    ) -> Result<(), StorageError> {
+        let expires_at = unix_seconds(expires_at);
+        self.with_transaction(|tx| {
+            for key in seats {
+                write_state(tx, event, key, 'R', None)?;
+                tx.execute(
+                    "INSERT OR REPLACE INTO holds
+                        (event, section, row, number, session, expires_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                    params![event, key.0, key.1, key.2, session, expires_at],
+                )?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Guarda la liberación de los asientos del evento y borra sus reservas temporales.
+    pub fn release_seats(&self, event: &str, seats: &[SeatKey]) -> Result<(), StorageError> {
+        self.with_transaction(|tx| {
+            for key in seats {
+                write_state(tx, event, key, 'F', None)?;
+                delete_hold(tx, event, key)?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Guarda la venta de los asientos junto con su orden y sus entradas.
+    /// Los topes por sesión y por cliente de cada categoría del recinto se verifican en la
+    /// misma transacción, contando las órdenes ya guardadas; si alguno se supera no se
+    /// guarda nada y se retorna `StorageError::Limit`.
+    pub fn book_seats(
+        &self,
+        session: SessionId,
+        order: &Order,
+        venue: &Venue,
+    ) -> Result<(), StorageError> {
+        self.with_transaction(|tx| {
+            tx.execute(
+                "INSERT INTO orders
+                    (number, id, access_key, event, customer, buyer, card_fingerprint, session,
+                     transaction_id, total_price, status, created_at, paid_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+                params![
+                    order.number,
+                    order.id,
+                    order.access_key,
+                    order.event,
+                    order.customer,
+                    order.buyer,
+                    order.card_fingerprint,
+                    session,
+                    order.transaction_id,
+                    order.total_price,
+                    order.status.as_str(),
+                    order.created_at,
+                    order.paid_at,
+                ],
+            )?;
+            for ticket in &order.tickets {
+                let key = ticket.seat();
+                write_state(tx, &order.event, &key, 'B', Some(&order.id))?;
+                delete_hold(tx, &order.event, &key)?;
+                tx.execute(
+                    "INSERT INTO tickets (id, order_id, section, row, number, category, price)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                    params![
+                        ticket.id,
+                        order.id,
+                        ticket.section,
+                        ticket.row,
+                        ticket.number,
+                        ticket.category,
+                        ticket.price
+                    ],
+                )?;
+            }
+            check_limits(tx, order, venue)
+        })
+    }
+
+    /// Asientos de la categoría que compró en el evento la sesión o la tarjeta indicada.
+    pub fn purchased_seats(
+        &self,
+        event: &str,
+        category: &str,
+        purchaser: Purchaser,
+    ) -> Result<u32, StorageError> {
+        let conn = self.conn.lock().unwrap();
+        count_purchased(&conn, event, category, purchaser)
+    }
+
+    /// Guarda los asientos que quedaron como separación alrededor de la orden.
+    pub fn buffer_seats(&self, order: &Order, seats: &[SeatKey]) -> Result<(), StorageError> {
+        self.with_transaction(|tx| {
+            for key in seats {
+                write_state(tx, &order.event, key, 'D', Some(&order.id))?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Número de la última orden guardada, o 0 si no hay ninguna.
+    pub fn last_order_number(&self) -> Result<u64, StorageError> {
+        let conn = self.conn.lock().unwrap();
+        let last: Option<u64> =
+            conn.query_row("SELECT MAX(number) FROM orders", [], |row| row.get(0))?;
+        Ok(last.unwrap_or(0))
+    }
+
+    /// Busca una orden por su identificador.
+    pub fn load_order(&self, id: &str) -> Result<Option<Order>, StorageError> {
+        let conn = self.conn.lock().unwrap();
+        let order = conn
+            .query_row(
+                &format!("{} WHERE id = ?1", SELECT_ORDERS),
+                params![id],
+                read_order,
+            )
+            .optional()?;
+        match order {
+            Some(order) => Ok(Some(with_tickets(&conn, order)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Busca las órdenes de un cliente, de la más antigua a la más reciente.
+    pub fn load_customer_orders(&self, customer: &str) -> Result<Vec<Order>, StorageError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "{} WHERE customer = ?1 ORDER BY number",
+            SELECT_ORDERS
+        ))?;
+        let orders = stmt
+            .query_map(params![customer], read_order)?
+            .collect::<Result<Vec<_>, _>>()?;
+        orders
+            .into_iter()
+            .map(|order| with_tickets(&conn, order))
+            .collect()
+    }
+
+    /// Ejecuta `apply` dentro de una transacción que se confirma solo si no hubo errores.
+    fn with_transaction<F>(&self, apply: F) -> Result<(), StorageError>
+    where
+        F: FnOnce(&Transaction) -> Result<(), StorageError>,
+    {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        apply(&tx)?;
+        tx.commit()?;
+        Ok(())
+    }
+}
+
+/// Función auxiliar para guardar el estado de un asiento.
+/// `detail` guarda la orden de un asiento vendido o de separación, o el motivo de uno bloqueado.
+fn write_state(
+    tx: &Transaction,
+    event: &str,
+    (section, row, number): &SeatKey,
+    state: char,
+    detail: Option<&str>,
+) -> Result<(), StorageError> {
+    tx.execute(
+        "INSERT INTO seats (event, section, row, number, state, detail)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+         ON CONFLICT (event, section, row, number)
+         DO UPDATE SET state = excluded.state, detail = excluded.detail",
+        params![event, section, row, number, state.to_string(), detail],
+    )?;
+    Ok(())
+}
+
+/// Función auxiliar para guardar el estado de un asiento a partir de su `SeatStatus`.
+fn write_status(
+    tx: &Transaction,
+    event: &str,
+    key: &SeatKey,
+    status: &SeatStatus,
+) -> Result<(), StorageError> {
+    let detail = match status {
+        SeatStatus::Booked { order } | SeatStatus::Buffer { order } => Some(order.as_str()),
+        SeatStatus::Blocked { reason } => Some(reason.as_str()),
+        SeatStatus::Free | SeatStatus::Held { .. } => None,
+    };
+    write_state(tx, event, key, status.code(), detail)
+}
+
+/// Consulta base para leer órdenes; sus columnas coinciden con `read_order`.
+const SELECT_ORDERS: &str =
+    "SELECT number, id, customer, transaction_id, total_price, status, created_at, paid_at, event,
+            access_key, buyer, card_fingerprint
+     FROM orders";
+
+/// Función auxiliar para leer una orden, todavía sin sus entradas.
+fn read_order(row: &rusqlite::Row) -> rusqlite::Result<Order> {
+    let status: String = row.get(5)?;
+    let status = OrderStatus::parse(&status).ok_or_else(|| {
+        rusqlite::Error::FromSqlConversionFailure(
+            5,
+            rusqlite::types::Type::Text,
+            format!("estado de orden desconocido: {:?}", status).into(),
+        )
+    })?;
+    Ok(Order {
+        number: row.get(0)?,
+        id: row.get(1)?,
+        access_key: row.get(9)?,
+        event: row.get(8)?,
+        customer: row.get(2)?,
+        buyer: row.get(10)?,
+        card_fingerprint: row.get(11)?,
+        tickets: Vec::new(),
+        transaction_id: row.get(3)?,
+        total_price: row.get(4)?,
+        status,
+        created_at: row.get(6)?,
+        paid_at: row.get(7)?,
+    })
+}
+
+/// Función auxiliar para completar una orden con sus entradas.
+fn with_tickets(conn: &Connection, mut order: Order) -> Result<Order, StorageError> {
+    let mut stmt = conn.prepare(
+        "SELECT id, section, row, number, category, price FROM tickets
+         WHERE order_id = ?1 ORDER BY rowid",
+    )?;
+    order.tickets = stmt
+        .query_map(params![order.id], |row| {
+            Ok(Ticket {
+                id: row.get(0)?,
+                section: row.get(1)?,
+                row: row.get(2)?,
+                number: row.get(3)?,
+                category: row.get(4)?,
+                price: row.get(5)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(order)
+}
+
+/// Función auxiliar para verificar, con la orden ya guardada en la transacción, que la sesión
+/// y la tarjeta que la pagaron no superen los topes de compra de cada categoría de la orden.
+fn check_limits(tx: &Transaction, order: &Order, venue: &Venue) -> Result<(), StorageError> {
+    let mut categories: Vec<&str> = order.tickets.iter().map(|t| t.category.as_str()).collect();
+    categories.sort_unstable();
+    categories.dedup();
+    for category in categories {
+        let Some(category) = venue.category(category) else {
+            continue;
+        };
+        let ordered = order
+            .tickets
+            .iter()
+            .filter(|ticket| ticket.category == category.id)
+            .count() as u32;
+        let caps = [
+            (
+                category.limits.per_session,
+                Purchaser::Buyer(&order.buyer),
+                "por sesión",
+            ),
+            (
+                category.limits.per_customer,
+                Purchaser::Card(&order.card_fingerprint),
+                "por cliente",
+            ),
+        ];
+        for (limit, purchaser, scope) in caps {
+            let Some(limit) = limit else {
+                continue;
+            };
+            let bought = count_purchased(tx, &order.event, &category.id, purchaser)?;
+            if bought > limit {
+                return Err(StorageError::Limit(format!(
+                    "Se pueden comprar hasta {} asientos {} {} en cada evento y ya se compraron {}",
+                    limit,
+                    category.name,
+                    scope,
+                    bought - ordered
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Función auxiliar para contar los asientos de una categoría que compró en un evento la
+/// sesión o la tarjeta indicada.
+fn count_purchased(
+    conn: &Connection,
+    event: &str,
+    category: &str,
+    purchaser: Purchaser,
+) -> Result<u32, StorageError> {
+    let (column, value) = match purchaser {
+        Purchaser::Buyer(buyer) => ("buyer", buyer),
+        Purchaser::Card(fingerprint) => ("card_fingerprint", fingerprint),
+    };
+    let count: u32 = conn.query_row(
+        &format!(
+            "SELECT COUNT(*) FROM tickets JOIN orders ON orders.id = tickets.order_id
+             WHERE orders.event = ?1 AND tickets.category = ?2 AND orders.{} = ?3",
+            column
+        ),
+        params![event, category, value],
+        |row| row.get(0),
+    )?;
+    Ok(count)
+}
+
+/// Función auxiliar para borrar la reserva temporal de un asiento de un evento.
+fn delete_hold(
+    tx: &Transaction,
+    event: &str,
+    (section, row, number): &SeatKey,
+) -> Result<(), StorageError> {
+    tx.execute(
+        "DELETE FROM holds WHERE event = ?1 AND section = ?2 AND row = ?3 AND number = ?4",
+        params![event, section, row, number],
+    )?;
+    Ok(())
+}
+
+/// Función auxiliar para convertir un instante en segundos desde la época Unix.
+fn unix_seconds(time: SystemTime) -> i64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::seat_manager::row_of_seats;
+    use crate::venue::parse_venue;
+    use std::time::Duration;
+
+    const EVENT: &str = "2026-11-06";
+
+    fn venue() -> Venue {
+        parse_venue(
+            r#"
+            name = "Sala de prueba"
+
+            [[category]]
+            id = "Pista"
+            max_per_session = 2
+            max_per_customer = 3
+
+            [[section]]
+            id = "F"
+            category = "Pista"
+            visibility = 100.0
+            price = 30.0
+            rows = [6]
+            "#,
+        )
+        .unwrap()
+    }
+
+    fn order(number: u64, buyer: &str, card: &str, seats: &[u32]) -> Order {
+        let id = format!("ORD-{}", number);
+        Order {
+            number,
+            id: id.clone(),
+            access_key: format!("clave-{}", number),
+            event: EVENT.to_string(),
+            customer: "cliente@example.com".to_string(),
+            buyer: buyer.to_string(),
+            card_fingerprint: card.to_string(),
+            tickets: seats
+                .iter()
+                .map(|&seat| Ticket {
+                    id: format!("TKT-{}-{}", number, seat),
+                    section: "F".to_string(),
+                    row: 1,
+                    number: seat,
+                    category: "Pista".to_string(),
+                    price: 30.0,
+                })
+                .collect(),
+            transaction_id: format!("mock-{}", number),
+            total_price: 30.0 * seats.len() as f32,
+            status: OrderStatus::Paid,
+            created_at: 0,
+            paid_at: 0,
+        }
+    }
+
+    fn seat(number: u32) -> SeatKey {
+        ("F".to_string(), 1, number)
+    }
+
+    fn free_row() -> SeatMap {
+        row_of_seats(&[SeatStatus::Free, SeatStatus::Free, SeatStatus::Free])
+    }
+
+    fn stored_seat(storage: &Storage, number: u32) -> Option<String> {
+        storage
+            .conn
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT state FROM seats WHERE event = ?1 AND number = ?2",
+                params![EVENT, number],
+                |row| row.get(0),
+            )
+            .optional()
+            .unwrap()
+    }
+
+    fn stored_tickets(storage: &Storage) -> i64 {
+        storage
+            .conn
+            .lock()
+            .unwrap()
+            .query_row("SELECT COUNT(*) FROM tickets", [], |row| row.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn session_over_its_cap_stores_nothing() {
+        let storage = Storage::open_in_memory();
+        let venue = venue();
+        storage
+            .book_seats(1, &order(1, "sesion-1", "tarjeta-1", &[1, 2]), &venue)
+            .unwrap();
+
+        let result = storage.book_seats(1, &order(2, "sesion-1", "tarjeta-2", &[3]), &venue);
+        assert!(matches!(result, Err(StorageError::Limit(_))));
+        assert!(storage.load_order("ORD-2").unwrap().is_none());
+        assert_eq!(stored_tickets(&storage), 2);
+        assert_eq!(stored_seat(&storage, 3), None);
+    }
+
+    #[test]
+    fn card_over_its_cap_stores_nothing_across_sessions() {
+        let storage = Storage::open_in_memory();
+        let venue = venue();
+        storage
+            .book_seats(1, &order(1, "sesion-1", "tarjeta-1", &[1, 2]), &venue)
+            .unwrap();
+
+        let result = storage.book_seats(2, &order(2, "sesion-2", "tarjeta-1", &[3, 4]), &venue);
+        assert!(matches!(result, Err(StorageError::Limit(_))));
+        assert!(storage.load_order("ORD-2").unwrap().is_none());
+        assert_eq!(stored_tickets(&storage), 2);
+        assert_eq!(
+            storage
+                .purchased_seats(EVENT, "Pista", Purchaser::Card("tarjeta-1"))
+                .unwrap(),
+            2
+        );
+    }
+
+    #[test]
+    fn order_within_the_caps_is_stored() {
+        let storage = Storage::open_in_memory();
+        let venue = venue();
+        storage
+            .book_seats(1, &order(1, "sesion-1", "tarjeta-1", &[1, 2]), &venue)
+            .unwrap();
+        storage
+            .book_seats(2, &order(2, "sesion-2", "tarjeta-1", &[3]), &venue)
+            .unwrap();
+
+        let stored = storage.load_order("ORD-2").unwrap().unwrap();
+        assert_eq!(stored.tickets.len(), 1);
+        assert_eq!(stored_seat(&storage, 3).as_deref(), Some("B"));
+        assert_eq!(
+            storage
+                .purchased_seats(EVENT, "Pista", Purchaser::Card("tarjeta-1"))
+                .unwrap(),
+            3
+        );
+    }
+
+    #[test]
+    fn restore_frees_held_seats_and_keeps_booked_ones() {
+        let storage = Storage::open_in_memory();
+        let expires_at = SystemTime::now() + Duration::from_secs(60);
+        storage
+            .hold_seats(EVENT, 1, &[seat(1)], expires_at)
+            .unwrap();
+        storage
+            .book_seats(2, &order(1, "sesion-2", "tarjeta-2", &[2]), &venue())
+            .unwrap();
+
+        let seats = free_row();
+        storage.restore(EVENT, &seats).unwrap();
+
+        let seats_guard = seats.lock().unwrap();
+        assert_eq!(seats_guard[&seat(1)].status, SeatStatus::Free);
+        assert_eq!(
+            seats_guard[&seat(2)].status,
+            SeatStatus::Booked {
+                order: "ORD-1".to_string()
+            }
+        );
+        assert_eq!(stored_seat(&storage, 1).as_deref(), Some("F"));
+        let holds: i64 = storage
+            .conn
+            .lock()
+            .unwrap()
+            .query_row("SELECT COUNT(*) FROM holds", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(holds, 0);
+    }
+
+    #[test]
+    fn restore_rejects_a_booked_seat_without_order() {
+        let storage = Storage::open_in_memory();
+        storage
+            .with_transaction(|tx| write_state(tx, EVENT, &seat(1), 'B', None))
+            .unwrap();
+
+        let seats = free_row();
+        assert!(matches!(
+            storage.restore(EVENT, &seats),
+            Err(StorageError::Corrupt(_))
+        ));
+        assert_eq!(seats.lock().unwrap()[&seat(1)].status, SeatStatus::Free);
+    }
+}
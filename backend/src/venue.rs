@@ -0,0 +1,417 @@
+// venue.rs
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// Identificador de una sección del recinto (por ejemplo, "A1").
+pub type SectionId = String;
+
+/// Identificador de una categoría del recinto (por ejemplo, "VIP").
+pub type CategoryId = String;
+
+/// Definición declarativa de un recinto, leída desde un archivo TOML.
+#[derive(Debug, Deserialize)]
+pub struct VenueConfig {
+    pub name: String,
+    #[serde(rename = "category")]
+    pub categories: Vec<CategoryConfig>,
+    #[serde(rename = "section")]
+    pub sections: Vec<SectionConfig>,
+}
+
+/// Definición de una categoría tal como aparece en el archivo.
+#[derive(Debug, Deserialize)]
+pub struct CategoryConfig {
+    pub id: CategoryId,
+    pub name: Option<String>,
+    pub order: Option<u32>,
+    /// Categoría a la que se recurre cuando esta no tiene disponibilidad.
+    pub downgrade: Option<CategoryId>,
+    /// Asientos de la categoría que se pueden comprar en una orden.
+    pub max_per_order: Option<u32>,
+    /// Asientos de la categoría que puede comprar una sesión en cada evento.
+    pub max_per_session: Option<u32>,
+    /// Asientos de la categoría que se pueden pagar con una misma tarjeta en cada evento.
+    pub max_per_customer: Option<u32>,
+}
+
+/// Definición de una sección tal como aparece en el archivo.
+#[derive(Debug, Deserialize)]
+pub struct SectionConfig {
+    pub id: SectionId,
+    pub name: Option<String>,
+    pub order: Option<u32>,
+    pub category: CategoryId,
+    pub visibility: f32,
+    pub price: f32,
+    /// Cantidad de asientos de cada fila, empezando por la fila 1.
+    pub rows: Vec<u32>,
+    /// Asientos con características especiales (espacios para silla de ruedas, pasillo, etc.).
+    #[serde(default, rename = "seat")]
+    pub seats: Vec<SeatGroupConfig>,
+}
+
+/// Grupo de asientos de una fila que comparten características especiales.
+#[derive(Debug, Deserialize)]
+pub struct SeatGroupConfig {
+    pub row: u32,
+    pub numbers: Vec<u32>,
+    pub attributes: Vec<SeatAttribute>,
+}
+
+/// Característica especial de un asiento.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SeatAttribute {
+    /// Espacio para silla de ruedas.
+    Wheelchair,
+    /// Asiento para el acompañante de una persona en silla de ruedas.
+    Companion,
+    /// Asiento con visibilidad reducida.
+    RestrictedView,
+    /// Asiento junto al pasillo.
+    Aisle,
+}
+
+/// Características especiales de un asiento.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct SeatAttributes {
+    pub wheelchair: bool,
+    pub companion: bool,
+    pub restricted_view: bool,
+    pub aisle: bool,
+}
+
+impl SeatAttributes {
+    /// Agrega una característica.
+    fn add(&mut self, attribute: SeatAttribute) {
+        match attribute {
+            SeatAttribute::Wheelchair => self.wheelchair = true,
+            SeatAttribute::Companion => self.companion = true,
+            SeatAttribute::RestrictedView => self.restricted_view = true,
+            SeatAttribute::Aisle => self.aisle = true,
+        }
+    }
+
+    /// Indica si el asiento está reservado para personas en silla de ruedas y sus acompañantes.
+    pub fn is_accessible(&self) -> bool {
+        self.wheelchair || self.companion
+    }
+}
+
+/// Categoría del recinto, ya validada.
+#[derive(Debug, Clone)]
+pub struct Category {
+    pub id: CategoryId,
+    pub name: String,
+    pub order: u32,
+    pub downgrade: Option<CategoryId>,
+    pub limits: PurchaseLimits,
+}
+
+/// Topes de compra de una categoría; `None` significa sin tope.
+#[derive(Debug, Clone, Copy)]
+pub struct PurchaseLimits {
+    pub per_order: Option<u32>,
+    pub per_session: Option<u32>,
+    pub per_customer: Option<u32>,
+}
+
+/// Sección del recinto, ya validada.
+#[derive(Debug, Clone)]
+pub struct Section {
+    pub id: SectionId,
+    pub name: String,
+    pub order: u32,
+    pub category: CategoryId,
+    pub visibility: f32,
+    pub price: f32,
+    pub rows: Vec<u32>,
+    /// Características especiales de los asientos, por fila y número.
+    pub attributes: HashMap<(u32, u32), SeatAttributes>,
+}
+
+impl Section {
+    /// Características especiales de un asiento (ninguna si no se definieron).
+    pub fn seat_attributes(&self, row: u32, number: u32) -> SeatAttributes {
+        self.attributes
+            .get(&(row, number))
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+/// Recinto con sus categorías y secciones ordenadas para mostrarse.
+#[derive(Debug, Clone)]
+pub struct Venue {
+    pub name: String,
+    pub categories: Vec<Category>,
+    pub sections: Vec<Section>,
+}
+
+/// Errores posibles al cargar la definición de un recinto.
+#[derive(Debug)]
+pub enum VenueError {
+    Io(std::io::Error),
+    Parse(toml::de::Error),
+    Invalid(String),
+}
+
+impl fmt::Display for VenueError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VenueError::Io(e) => write!(f, "no se pudo leer el archivo del recinto: {}", e),
+            VenueError::Parse(e) => write!(f, "formato de recinto inválido: {}", e),
+            VenueError::Invalid(msg) => write!(f, "definición de recinto inválida: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for VenueError {}
+
+impl From<std::io::Error> for VenueError {
+    fn from(e: std::io::Error) -> Self {
+        VenueError::Io(e)
+    }
+}
+
+impl From<toml::de::Error> for VenueError {
+    fn from(e: toml::de::Error) -> Self {
+        VenueError::Parse(e)
+    }
+}
+
+/// Función para cargar y validar la definición de un recinto desde un archivo.
+pub fn load_venue(path: &Path) -> Result<Venue, VenueError> {
+    let contents = fs::read_to_string(path)?;
+    parse_venue(&contents)
+}
+
+/// Función para interpretar y validar la definición de un recinto en formato TOML.
+pub fn parse_venue(contents: &str) -> Result<Venue, VenueError> {
+    let config: VenueConfig = toml::from_str(contents)?;
+    Venue::from_config(config)
+}
+
+impl Venue {
+    /// Construye el recinto a partir de su definición, verificando que sea coherente.
+    pub fn from_config(config: VenueConfig) -> Result<Venue, VenueError> {
+        if config.categories.is_empty() {
+            return Err(VenueError::Invalid("el recinto no define categorías".to_string()));
+        }
+        if config.sections.is_empty() {
+            return Err(VenueError::Invalid("el recinto no define secciones".to_string()));
+        }
+
+        let mut category_ids = HashSet::new();
+        for category in &config.categories {
+            if !category_ids.insert(category.id.as_str()) {
+                return Err(VenueError::Invalid(format!(
+                    "la categoría {} está definida más de una vez",
+                    category.id
+                )));
+            }
+        }
+
+        for category in &config.categories {
+            let limits = [
+                category.max_per_order,
+                category.max_per_session,
+                category.max_per_customer,
+            ];
+            if limits.contains(&Some(0)) {
+                return Err(VenueError::Invalid(format!(
+                    "los topes de compra de la categoría {} deben ser mayores que cero",
+                    category.id
+                )));
+            }
+
+            if let Some(downgrade) = &category.downgrade {
+                if !category_ids.contains(downgrade.as_str()) {
+                    return Err(VenueError::Invalid(format!(
+                        "la categoría {} desciende a {}, que no existe",
+                        category.id, downgrade
+                    )));
+                }
+            }
+        }
+
+        let mut section_ids = HashSet::new();
+        for section in &config.sections {
+            if !section_ids.insert(section.id.as_str()) {
+                return Err(VenueError::Invalid(format!(
+                    "la sección {} está definida más de una vez",
+                    section.id
+                )));
+            }
+
+            if !category_ids.contains(section.category.as_str()) {
+                return Err(VenueError::Invalid(format!(
+                    "la sección {} pertenece a la categoría {}, que no existe",
+                    section.id, section.category
+                )));
+            }
+
+            if !section.visibility.is_finite() || section.visibility < 0.0 {
+                return Err(VenueError::Invalid(format!(
+                    "la sección {} tiene una visibilidad inválida",
+                    section.id
+                )));
+            }
+
+            // Los asientos se pagan siempre con tarjeta, así que no hay secciones gratuitas
+            if !section.price.is_finite() || section.price <= 0.0 {
+                return Err(VenueError::Invalid(format!(
+                    "la sección {} debe tener un precio mayor que cero",
+                    section.id
+                )));
+            }
+
+            if section.rows.is_empty() {
+                return Err(VenueError::Invalid(format!(
+                    "la sección {} no tiene filas",
+                    section.id
+                )));
+            }
+
+            if let Some(index) = section.rows.iter().position(|&count| count == 0) {
+                return Err(VenueError::Invalid(format!(
+                    "la fila {} de la sección {} no tiene asientos",
+                    index + 1,
+                    section.id
+                )));
+            }
+
+            for group in &section.seats {
+                let seat_count = match group.row.checked_sub(1) {
+                    Some(index) => section.rows.get(index as usize).copied(),
+                    None => None,
+                };
+                let Some(seat_count) = seat_count else {
+                    return Err(VenueError::Invalid(format!(
+                        "la sección {} no tiene la fila {}",
+                        section.id, group.row
+                    )));
+                };
+                if let Some(number) = group
+                    .numbers
+                    .iter()
+                    .find(|&&number| number == 0 || number > seat_count)
+                {
+                    return Err(VenueError::Invalid(format!(
+                        "la fila {} de la sección {} no tiene el asiento {}",
+                        group.row, section.id, number
+                    )));
+                }
+            }
+        }
+
+        for category in &config.categories {
+            if !config.sections.iter().any(|s| s.category == category.id) {
+                return Err(VenueError::Invalid(format!(
+                    "la categoría {} no tiene secciones",
+                    category.id
+                )));
+            }
+        }
+
+        let mut categories: Vec<Category> = config
+            .categories
+            .into_iter()
+            .enumerate()
+            .map(|(index, c)| Category {
+                name: c.name.unwrap_or_else(|| c.id.clone()),
+                order: c.order.unwrap_or(index as u32),
+                id: c.id,
+                downgrade: c.downgrade,
+                limits: PurchaseLimits {
+                    per_order: c.max_per_order,
+                    per_session: c.max_per_session,
+                    per_customer: c.max_per_customer,
+                },
+            })
+            .collect();
+        categories.sort_by_key(|c| c.order);
+
+        let mut sections: Vec<Section> = config
+            .sections
+            .into_iter()
+            .enumerate()
+            .map(|(index, s)| {
+                let mut attributes: HashMap<(u32, u32), SeatAttributes> = HashMap::new();
+                for group in &s.seats {
+                    for &number in &group.numbers {
+                        let seat = attributes.entry((group.row, number)).or_default();
+                        for &attribute in &group.attributes {
+                            seat.add(attribute);
+                        }
+                    }
+                }
+                Section {
+                    name: s.name.unwrap_or_else(|| s.id.clone()),
+                    order: s.order.unwrap_or(index as u32),
+                    id: s.id,
+                    category: s.category,
+                    visibility: s.visibility,
+                    price: s.price,
+                    rows: s.rows,
+                    attributes,
+                }
+            })
+            .collect();
+        sections.sort_by_key(|s| s.order);
+
+        let venue = Venue {
+            name: config.name,
+            categories,
+            sections,
+        };
+
+        // La cadena de descenso no puede volver a una categoría ya visitada.
+        for category in &venue.categories {
+            let mut visited = vec![category.id.as_str()];
+            let mut current = category;
+            while let Some(lower) = venue.lower_category(&current.id) {
+                if visited.contains(&lower.id.as_str()) {
+                    return Err(VenueError::Invalid(format!(
+                        "la cadena de descenso de la categoría {} forma un ciclo",
+                        category.id
+                    )));
+                }
+                visited.push(&lower.id);
+                current = lower;
+            }
+        }
+
+        Ok(venue)
+    }
+
+    /// Función para obtener una categoría por su identificador.
+    pub fn category(&self, id: &str) -> Option<&Category> {
+        self.categories.iter().find(|c| c.id == id)
+    }
+
+    /// Función para obtener una sección por su identificador.
+    pub fn section(&self, id: &str) -> Option<&Section> {
+        self.sections.iter().find(|s| s.id == id)
+    }
+
+    /// Función para obtener la categoría inferior según la cadena de descenso configurada.
+    pub fn lower_category(&self, category: &str) -> Option<&Category> {
+        self.category(category)
+            .and_then(|c| c.downgrade.as_deref())
+            .and_then(|id| self.category(id))
+    }
+
+    /// Función para obtener la categoría superior: la que desciende a la indicada.
+    /// Si varias descienden a ella se elige la más cercana según el orden de visualización.
+    pub fn higher_category(&self, category: &str) -> Option<&Category> {
+        self.categories
+            .iter()
+            .rev()
+            .find(|c| c.downgrade.as_deref() == Some(category))
+    }
+}
@@ -0,0 +1,371 @@
+// waiting_room.rs
+
+use crate::orders::{random_token, unix_now};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tokio::time::MissedTickBehavior;
+
+/// Turno de una sesión en la fila de espera; se entregan en orden de llegada.
+pub type Ticket = u64;
+
+/// Resultado de entrar a la sala de espera.
+#[derive(Debug, Clone)]
+pub enum Admission {
+    /// La sala está desactivada: la sesión compra sin esperar ni recibir token.
+    Open,
+    /// La sesión espera su turno.
+    Waiting(Ticket),
+    /// La sesión fue admitida con este token.
+    Admitted(AdmissionToken),
+}
+
+/// Token que permite volver a entrar sin hacer la fila mientras no venza.
+#[derive(Debug, Clone)]
+pub struct AdmissionToken {
+    pub token: String,
+    /// Segundos desde la época Unix en que vence el token.
+    pub expires_at: u64,
+}
+
+/// Token emitido, con su vencimiento y si alguna sesión conectada lo está usando.
+struct IssuedToken {
+    expires_at: Instant,
+    expires_at_unix: u64,
+    in_use: bool,
+}
+
+/// Estado de la fila protegido por el mutex de la sala.
+struct Queue {
+    next_ticket: Ticket,
+    /// Turnos de las sesiones que siguen esperando, del más antiguo al más nuevo.
+    waiting: BTreeSet<Ticket>,
+    /// Hay un lugar libre que no se usó porque nadie esperaba.
+    slot_available: bool,
+    tokens: HashMap<String, IssuedToken>,
+}
+
+/// Sala de espera que admite sesiones a un ritmo fijo antes de dejarlas comprar.
+pub struct WaitingRoom {
+    /// Tiempo entre dos admisiones; `None` si la sala está desactivada.
+    interval: Option<Duration>,
+    token_ttl: Duration,
+    queue: Mutex<Queue>,
+    /// Último turno admitido; cada sesión que espera calcula su posición a partir de él.
+    admitted: watch::Sender<Ticket>,
+}
+
+impl WaitingRoom {
+    /// Crea la sala; con `admissions_per_minute` en cero la sala está desactivada.
+    pub fn new(admissions_per_minute: u64, token_ttl: Duration) -> Self {
+        let interval = (admissions_per_minute > 0)
+            .then(|| Duration::from_secs(60).div_f64(admissions_per_minute as f64));
+        let (admitted, _) = watch::channel(0);
+        WaitingRoom {
+            interval,
+            token_ttl,
+            queue: Mutex::new(Queue {
+                next_ticket: 1,
+                waiting: BTreeSet::new(),
+                slot_available: true,
+                tokens: HashMap::new(),
+            }),
+            admitted,
+        }
+    }
+
+    /// Indica si las sesiones deben hacer la fila.
+    pub fn is_enabled(&self) -> bool {
+        self.interval.is_some()
+    }
+
+    /// Entra a la fila. Si nadie espera y hay un lugar libre, la sesión se admite enseguida.
+    /// El lugar sale de la fila o deja libre su token al soltarse.
+    pub fn join(self: &Arc<Self>) -> Place {
+        Place {
+            room: Arc::clone(self),
+            admission: self.enter(),
+        }
+    }
+
+    /// Función auxiliar para tomar un turno, o admitir enseguida si hay un lugar libre.
+    fn enter(&self) -> Admission {
+        if !self.is_enabled() {
+            return Admission::Open;
+        }
+        let mut queue = self.queue.lock().unwrap();
+        let ticket = queue.next_ticket;
+        queue.next_ticket += 1;
+        if queue.waiting.is_empty() && queue.slot_available {
+            queue.slot_available = false;
+            self.admitted.send_replace(ticket);
+            return Admission::Admitted(self.issue_token(&mut queue));
+        }
+        queue.waiting.insert(ticket);
+        Admission::Waiting(ticket)
+    }
+
+    /// Sale de la fila sin haber sido admitida (la sesión se desconectó).
+    fn leave(&self, ticket: Ticket) {
+        self.queue.lock().unwrap().waiting.remove(&ticket);
+    }
+
+    /// Si ya le tocó el turno, admite la sesión y le entrega su token.
+    fn try_admit(&self, ticket: Ticket) -> Option<AdmissionToken> {
+        if ticket > *self.admitted.borrow() {
+            return None;
+        }
+        let mut queue = self.queue.lock().unwrap();
+        Some(self.issue_token(&mut queue))
+    }
+
+    /// Admite la sesión que presenta un token vigente que ninguna otra sesión esté usando.
+    /// La sesión deja su lugar en la fila.
+    fn redeem(&self, ticket: Ticket, token: &str) -> Option<AdmissionToken> {
+        let mut queue = self.queue.lock().unwrap();
+        let issued = queue.tokens.get_mut(token)?;
+        if issued.in_use || issued.expires_at <= Instant::now() {
+            return None;
+        }
+        issued.in_use = true;
+        let admission = AdmissionToken {
+            token: token.to_string(),
+            expires_at: issued.expires_at_unix,
+        };
+        queue.waiting.remove(&ticket);
+        Some(admission)
+    }
+
+    /// Deja el token libre para que la misma persona vuelva a entrar si se reconecta.
+    fn release_token(&self, token: &str) {
+        if let Some(issued) = self.queue.lock().unwrap().tokens.get_mut(token) {
+            issued.in_use = false;
+        }
+    }
+
+    /// Posición de un turno en la fila y segundos estimados hasta la admisión.
+    /// Los turnos abandonados se cuentan hasta que la fila los pasa, así que la posición
+    /// real puede ser menor.
+    fn position(&self, ticket: Ticket) -> (u64, u64) {
+        let position = ticket.saturating_sub(*self.admitted.borrow()).max(1);
+        let interval = self.interval.unwrap_or_default();
+        let eta = interval.mul_f64(position as f64).as_secs();
+        (position, eta)
+    }
+
+    /// Crea un receptor que se despierta con cada admisión.
+    fn subscribe(&self) -> watch::Receiver<Ticket> {
+        self.admitted.subscribe()
+    }
+
+    /// Admite al turno más antiguo que sigue esperando, o guarda el lugar si nadie espera.
+    fn admit_next(&self) {
+        let mut queue = self.queue.lock().unwrap();
+        let now = Instant::now();
+        queue
+            .tokens
+            .retain(|_, issued| issued.in_use || issued.expires_at > now);
+        match queue.waiting.pop_first() {
+            Some(ticket) => {
+                self.admitted.send_replace(ticket);
+            }
+            None => queue.slot_available = true,
+        }
+    }
+
+    /// Emite un token nuevo, ya en uso por la sesión admitida.
+    fn issue_token(&self, queue: &mut Queue) -> AdmissionToken {
+        let token = random_token();
+        let expires_at_unix = unix_now() + self.token_ttl.as_secs();
+        queue.tokens.insert(
+            token.clone(),
+            IssuedToken {
+                expires_at: Instant::now() + self.token_ttl,
+                expires_at_unix,
+                in_use: true,
+            },
+        );
+        AdmissionToken {
+            token,
+            expires_at: expires_at_unix,
+        }
+    }
+}
+
+/// Lugar de una sesión en la sala de espera. Al soltarse sale de la fila o deja libre su
+/// token para volver a entrar, también si la sesión termina por un error.
+pub struct Place {
+    room: Arc<WaitingRoom>,
+    admission: Admission,
+}
+
+impl Place {
+    pub fn admission(&self) -> &Admission {
+        &self.admission
+    }
+
+    /// Indica si la sesión ya salió de la fila.
+    pub fn is_admitted(&self) -> bool {
+        !matches!(self.admission, Admission::Waiting(_))
+    }
+
+    /// Posición en la fila y segundos estimados hasta la admisión, si la sesión espera.
+    pub fn position(&self) -> Option<(u64, u64)> {
+        match self.admission {
+            Admission::Waiting(ticket) => Some(self.room.position(ticket)),
+            _ => None,
+        }
+    }
+
+    /// Si la sesión espera y ya le tocó el turno, la admite y retorna su token.
+    pub fn try_admit(&mut self) -> Option<AdmissionToken> {
+        let Admission::Waiting(ticket) = self.admission else {
+            return None;
+        };
+        let admission = self.room.try_admit(ticket)?;
+        self.admission = Admission::Admitted(admission.clone());
+        Some(admission)
+    }
+
+    /// Si la sesión espera, la admite con un token emitido antes que siga vigente y libre.
+    pub fn redeem(&mut self, token: &str) -> Option<AdmissionToken> {
+        let Admission::Waiting(ticket) = self.admission else {
+            return None;
+        };
+        let admission = self.room.redeem(ticket, token)?;
+        self.admission = Admission::Admitted(admission.clone());
+        Some(admission)
+    }
+
+    /// Crea un receptor que se despierta con cada admisión.
+    pub fn subscribe(&self) -> watch::Receiver<Ticket> {
+        self.room.subscribe()
+    }
+}
+
+impl Drop for Place {
+    fn drop(&mut self) {
+        match &self.admission {
+            Admission::Open => {}
+            Admission::Waiting(ticket) => self.room.leave(*ticket),
+            Admission::Admitted(admission) => self.room.release_token(&admission.token),
+        }
+    }
+}
+
+/// Inicia la tarea que admite sesiones de la fila al ritmo configurado.
+pub fn spawn_admitter(room: Arc<WaitingRoom>) -> Option<JoinHandle<()>> {
+    let interval = room.interval?;
+    Some(tokio::spawn(async move {
+        let mut ticks = tokio::time::interval(interval);
+        // Si el servidor se atrasa, no se admiten varias sesiones de golpe
+        ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            ticks.tick().await;
+            room.admit_next();
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn room(token_ttl: Duration) -> Arc<WaitingRoom> {
+        Arc::new(WaitingRoom::new(60, token_ttl))
+    }
+
+    fn token(place: &Place) -> String {
+        match place.admission() {
+            Admission::Admitted(admission) => admission.token.clone(),
+            other => panic!("la sesión no fue admitida: {:?}", other),
+        }
+    }
+
+    fn waiting(room: &WaitingRoom) -> Vec<Ticket> {
+        room.queue.lock().unwrap().waiting.iter().copied().collect()
+    }
+
+    #[test]
+    fn first_session_takes_the_free_slot_and_the_rest_wait() {
+        let room = room(Duration::from_secs(60));
+        let first = room.join();
+        let second = room.join();
+        let third = room.join();
+
+        assert!(first.is_admitted());
+        assert!(matches!(second.admission(), Admission::Waiting(2)));
+        assert!(matches!(third.admission(), Admission::Waiting(3)));
+        assert!(!room.queue.lock().unwrap().slot_available);
+        assert_eq!(waiting(&room), vec![2, 3]);
+        assert_eq!(third.position(), Some((2, 2)));
+    }
+
+    #[test]
+    fn sessions_are_admitted_in_arrival_order() {
+        let room = room(Duration::from_secs(60));
+        let _first = room.join();
+        let mut second = room.join();
+        let mut third = room.join();
+
+        room.admit_next();
+        assert!(third.try_admit().is_none());
+        assert!(second.try_admit().is_some());
+        assert!(second.is_admitted());
+
+        room.admit_next();
+        assert!(third.try_admit().is_some());
+
+        // Sin nadie esperando, la próxima admisión queda guardada para quien llegue
+        room.admit_next();
+        assert!(room.queue.lock().unwrap().slot_available);
+        assert!(room.join().is_admitted());
+    }
+
+    #[test]
+    fn redeem_rejects_unknown_and_used_tokens() {
+        let room = room(Duration::from_secs(60));
+        let first = room.join();
+        let mut second = room.join();
+
+        assert!(second.redeem("desconocido").is_none());
+        assert!(second.redeem(&token(&first)).is_none());
+
+        let used = token(&first);
+        drop(first);
+        assert!(second.redeem(&used).is_some());
+        assert_eq!(token(&second), used);
+        assert!(waiting(&room).is_empty());
+    }
+
+    #[test]
+    fn redeem_rejects_an_expired_token() {
+        let room = room(Duration::ZERO);
+        let first = room.join();
+        let expired = token(&first);
+        drop(first);
+
+        let mut late = room.join();
+        assert!(late.redeem(&expired).is_none());
+        assert!(!late.is_admitted());
+    }
+
+    #[test]
+    fn dropping_a_place_leaves_the_line_or_frees_its_token() {
+        let room = room(Duration::from_secs(60));
+        let first = room.join();
+        let second = room.join();
+        let third = room.join();
+
+        drop(second);
+        assert_eq!(waiting(&room), vec![3]);
+
+        let used = token(&first);
+        drop(first);
+        assert!(!room.queue.lock().unwrap().tokens[&used].in_use);
+        drop(third);
+        assert!(waiting(&room).is_empty());
+    }
+}
@@ -7,12 +7,23 @@ import SeatSuggestionList from './SeatSuggestions'; // Asegúrate de importar co
 import { useWebSocket } from '../contexts/WebSocketContext';
 import { useNavigate } from 'react-router-dom';
 
+// Estado de venta de cada función, tal como se muestra al usuario
+const STATUS_LABELS = {
+  draft: 'en preparación',
+  presale: 'en preventa',
+  on_sale: 'a la venta',
+  paused: 'con la venta pausada',
+  sold_out: 'agotada',
+  closed: 'con la venta cerrada',
+};
+
 class HomeContent extends React.Component {
   constructor(props) {
     super(props);
     this.state = {
       seatCount: 1,
       selectedCategory: '',
+      accessCode: '',
       suggestedSeats: [],
       selectedSuggestionIndex: null,
       selectedSuggestion: null,
@@ -21,6 +32,7 @@ class HomeContent extends React.Component {
     this.handleSeatRequest = this.handleSeatRequest.bind(this);
     this.setSeatCount = this.setSeatCount.bind(this);
     this.setSelectedCategory = this.setSelectedCategory.bind(this);
+    this.setAccessCode = this.setAccessCode.bind(this);
     this.handleSuggestionSelect = this.handleSuggestionSelect.bind(this);
     this.handleAcceptSuggestion = this.handleAcceptSuggestion.bind(this);
     this.handleRejectSuggestion = this.handleRejectSuggestion.bind(this);
@@ -34,16 +46,20 @@ class HomeContent extends React.Component {
     this.setState({ selectedCategory: value });
   }
 
+  setAccessCode(value) {
+    this.setState({ accessCode: value });
+  }
+
   handleSeatRequest() {
     const { sendSeatRequest } = this.props;
-    const { seatCount, selectedCategory } = this.state;
+    const { seatCount, selectedCategory, accessCode } = this.state;
 
     if (!selectedCategory) {
       alert('Por favor, seleccione una categoría.');
       return;
     }
 
-    sendSeatRequest(seatCount, selectedCategory);
+    sendSeatRequest(seatCount, selectedCategory, accessCode.trim());
     this.setState({ showForm: false });
   }
 
@@ -80,8 +96,8 @@ class HomeContent extends React.Component {
   }
 
   handleRejectSuggestion() {
-    const { sendChoice } = this.props;
-    sendChoice(0);
+    const { sendReject } = this.props;
+    sendReject();
     this.setState({
       suggestedSeats: [],
       selectedSuggestionIndex: null,
@@ -97,16 +113,37 @@ class HomeContent extends React.Component {
       serverMessage,
       seatStates,
       paymentStatus,
+      venue,
+      queue,
+      events,
+      currentEvent,
+      selectEvent,
     } = this.props;
     const {
       seatCount,
       selectedCategory,
+      accessCode,
       suggestedSeats,
       selectedSuggestionIndex,
       showForm,
       selectedSuggestion,
     } = this.state;
 
+    // Mientras la sesión espera su turno no se muestran los asientos
+    if (queue) {
+      return (
+        <div className="flex flex-col items-center justify-center min-h-screen">
+          <h1 className="text-4xl font-bold m-2">Ticket Buddy</h1>
+          <div className="bg-blue-100 text-blue-800 p-4 rounded">
+            Está en la fila de espera: posición {queue.position}, unos {queue.etaSecs}{' '}
+            segundos.
+          </div>
+        </div>
+      );
+    }
+
+    const selectedEvent = events.find((event) => event.id === currentEvent);
+
     const processedSeatStates = seatStates.map((seat) => ({
       ...seat,
       section: seat.section.toString(),
@@ -118,6 +155,28 @@ class HomeContent extends React.Component {
       <div className="flex flex-col items-center justify-center min-h-screen">
         <h1 className="text-4xl font-bold m-2">Ticket Buddy</h1>
 
+        {/* Elegir la función cuando hay más de una a la venta */}
+        {events.length > 1 && (
+          <select
+            className="border rounded p-2 mb-4"
+            value={currentEvent || ''}
+            onChange={(e) => selectEvent(e.target.value)}
+          >
+            {events.map((event) => (
+              <option key={event.id} value={event.id}>
+                {event.name} ({STATUS_LABELS[event.status] || event.status})
+              </option>
+            ))}
+          </select>
+        )}
+
+        {/* Avisar cuando la función elegida no está a la venta */}
+        {selectedEvent && selectedEvent.status !== 'on_sale' && (
+          <div className="bg-yellow-200 text-yellow-800 p-4 rounded mb-4">
+            Esta función está {STATUS_LABELS[selectedEvent.status] || selectedEvent.status}.
+          </div>
+        )}
+
         {/* Mostrar mensaje de pago si existe */}
         {paymentStatus === 'success' && (
           <div className="bg-green-200 text-green-800 p-4 rounded mb-4">
@@ -148,8 +207,13 @@ class HomeContent extends React.Component {
             setSeatCount={this.setSeatCount}
             selectedCategory={selectedCategory}
             setSelectedCategory={this.setSelectedCategory}
+            // El código de preventa solo hace falta mientras la función está en preventa
+            showAccessCode={!!selectedEvent && selectedEvent.status === 'presale'}
+            accessCode={accessCode}
+            setAccessCode={this.setAccessCode}
             handleSeatRequest={this.handleSeatRequest}
             connected={connected}
+            categories={venue ? venue.categories : []}
           />
         )}
 
@@ -198,8 +262,14 @@ const HomeContentWithWebSocket = () => {
     seatStates,
     sendSeatRequest,
     sendChoice,
+    sendReject,
     paymentStatus,
     setPaymentStatus,
+    venue,
+    queue,
+    events,
+    currentEvent,
+    selectEvent,
   } = useWebSocket();
 
   return (
@@ -210,6 +280,12 @@ const HomeContentWithWebSocket = () => {
       seatStates={seatStates}
       sendSeatRequest={sendSeatRequest}
       sendChoice={sendChoice}
+      sendReject={sendReject}
+      venue={venue}
+      queue={queue}
+      events={events}
+      currentEvent={currentEvent}
+      selectEvent={selectEvent}
       navigate={navigate}
       paymentStatus={paymentStatus}
       setPaymentStatus={setPaymentStatus}
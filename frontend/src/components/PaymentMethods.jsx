@@ -6,7 +6,7 @@ import { useWebSocket } from '../contexts/WebSocketContext';
 import Cards from 'react-credit-cards-2';
 import 'react-credit-cards-2/dist/es/styles-compiled.css';
 
-function PaymentMethods({ amount, seats }) {
+function PaymentMethods({ amount }) {
   const [cardDetails, setCardDetails] = useState({
     number: '',
     expiry: '',
@@ -15,7 +15,7 @@ function PaymentMethods({ amount, seats }) {
     focus: '',
   });
 
-  const { setPaymentStatus, sendPaymentResult } = useWebSocket();
+  const { setPaymentStatus, sendPayment } = useWebSocket();
   const navigate = useNavigate();
 
   const handleInputChange = (e) => {
@@ -27,7 +27,7 @@ function PaymentMethods({ amount, seats }) {
     setCardDetails((prev) => ({ ...prev, focus: e.target.name }));
   };
 
-  const handlePayment = async () => {
+  const handlePayment = () => {
     const { number, expiry, cvc, name } = cardDetails;
 
     if (!number || !expiry || !cvc || !name) {
@@ -35,27 +35,11 @@ function PaymentMethods({ amount, seats }) {
       return;
     }
 
-    try {
-      // Simular el resultado del pago (50% de probabilidad de éxito)
-      const isSuccess = Math.random() < 0.5;
+    // El servidor cobra el monto de los asientos aceptados y responde con el resultado
+    setPaymentStatus(null);
+    sendPayment({ number, holder: name, expiry, cvc });
 
-      if (isSuccess) {
-        setPaymentStatus('success');
-        alert('Pago procesado exitosamente.');
-        // Enviar resultado al backend
-        sendPaymentResult(true, seats);
-      } else {
-        setPaymentStatus('failure');
-        alert('El pago ha fallado. Por favor, inténtalo de nuevo.');
-        // Enviar resultado al backend
-        sendPaymentResult(false, seats);
-      }
-
-      navigate('/'); // Regresar a la página principal
-    } catch (error) {
-      console.error('Error al procesar el pago:', error);
-      alert('Error al procesar el pago.');
-    }
+    navigate('/'); // Regresar a la página principal
   };
 
   return (
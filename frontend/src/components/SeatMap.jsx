@@ -107,7 +107,7 @@ SeatMap.propTypes = {
       section: PropTypes.string.isRequired,
       row: PropTypes.oneOfType([PropTypes.string, PropTypes.number]).isRequired,
       number: PropTypes.number.isRequired,
-      booked: PropTypes.string.isRequired, // 'F', 'B', 'R'
+      booked: PropTypes.string.isRequired, // 'F', 'B', 'R', 'X', 'D'
     })
   ).isRequired,
   suggestedSeats: PropTypes.arrayOf(
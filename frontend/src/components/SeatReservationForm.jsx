@@ -10,7 +10,15 @@ const SeatReservationForm = ({
   setSelectedCategory,
   handleSeatRequest,
   connected,
+  categories,
+  showAccessCode,
+  accessCode,
+  setAccessCode,
 }) => {
+  // El servidor indica cuántos asientos de la categoría se pueden comprar por orden
+  const category = categories.find((c) => c.id === selectedCategory);
+  const maxSeats = (category && category.max_per_order) || 10;
+
   return (
     <div className="w-1/2 flex flex-row justify-between">
       <div>
@@ -21,7 +29,7 @@ const SeatReservationForm = ({
           id="seatCount"
           type="number"
           min="1"
-          max="10"
+          max={maxSeats}
           value={seatCount}
           onChange={(e) => setSeatCount(e.target.value)}
           className="border border-gray-300 p-2 rounded-md w-32 mb-4"
@@ -33,6 +41,7 @@ const SeatReservationForm = ({
         </label>
         <div className="w-72">
           <Select
+            key={categories.length}
             label="Seleccione una categoría"
             animate={{
               mount: { y: 0 },
@@ -42,13 +51,30 @@ const SeatReservationForm = ({
             value={selectedCategory}
             onChange={(value) => setSelectedCategory(value)} // Maneja el valor seleccionado directamente
           >
-            <Option value="VIP">VIP</Option>
-            <Option value="Business">Business</Option>
-            <Option value="Economy">Economy</Option>
+            {categories.map((category) => (
+              <Option key={category.id} value={category.id}>
+                {category.name}
+              </Option>
+            ))}
           </Select>
         </div>
       </div>
 
+      {showAccessCode && (
+        <div>
+          <label htmlFor="accessCode" className="block text-lg mb-2">
+            Código de preventa:
+          </label>
+          <input
+            id="accessCode"
+            type="text"
+            value={accessCode}
+            onChange={(e) => setAccessCode(e.target.value)}
+            className="border border-gray-300 p-2 rounded-md w-40 mb-4"
+          />
+        </div>
+      )}
+
       <div className="flex items-center">
         <Button
           color="blue"
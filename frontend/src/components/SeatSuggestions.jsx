@@ -4,6 +4,29 @@ import React from 'react';
 import PropTypes from 'prop-types';
 import { Button } from '@material-tailwind/react';
 
+// Descripción de cómo quedan repartidos los asientos de una sugerencia
+const CONTIGUITY_LABELS = {
+  single_row: 'Juntos en una fila',
+  stacked_rows: 'Juntos en filas seguidas',
+  split_rows: 'Separados dentro de la sección',
+  split_sections: 'Separados en varias secciones',
+};
+
+// Nombre de cada característica especial de un asiento
+const ATTRIBUTE_LABELS = {
+  wheelchair: 'silla de ruedas',
+  companion: 'acompañante',
+  restricted_view: 'visibilidad reducida',
+  aisle: 'pasillo',
+};
+
+// Características especiales de un asiento, separadas por comas
+const attributeLabels = (seat) =>
+  Object.keys(ATTRIBUTE_LABELS)
+    .filter((attribute) => seat.attributes && seat.attributes[attribute])
+    .map((attribute) => ATTRIBUTE_LABELS[attribute])
+    .join(', ');
+
 /**
  * SeatSuggestionList Class Component
  *
@@ -35,15 +58,27 @@ class SeatSuggestionList extends React.Component {
             >
               <div>
                 <strong>Sugerencia {suggestion.suggestion_number}:</strong>
+                {suggestion.upgrade && (
+                  <span>
+                    {' '}Mejora a {suggestion.category}
+                    {suggestion.price_difference != null &&
+                      ` (+$${suggestion.price_difference.toFixed(2)})`}
+                  </span>
+                )}
               </div>
               <div>
                 {suggestion.seats.map((seat, idx) => (
                   <span key={idx}>
                     {seat.section}-Fila{seat.row}-Asiento{seat.number}
+                    {attributeLabels(seat) && ` (${attributeLabels(seat)})`}
                     {idx < suggestion.seats.length - 1 ? ', ' : ''}
                   </span>
                 ))}
               </div>
+              <div>
+                <strong>Distribución:</strong>{' '}
+                {CONTIGUITY_LABELS[suggestion.contiguity] || suggestion.contiguity}
+              </div>
               <div>
                 <strong>Precio Total:</strong> ${suggestion.total_price.toFixed(2)}
               </div>
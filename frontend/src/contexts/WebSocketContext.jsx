@@ -5,45 +5,115 @@ import WebSocketInstance from '../services/WebSocketService';
 
 const WebSocketContext = createContext();
 
+// Clave donde se guarda el token de admisión para volver a entrar sin hacer la fila
+const ADMISSION_TOKEN_KEY = 'ticketbuddy.admission_token';
+
 export const WebSocketProvider = ({ children }) => {
   const [connected, setConnected] = useState(false);
   const [suggestions, setSuggestions] = useState([]);
   const [serverMessage, setServerMessage] = useState('');
   const [seatStates, setSeatStates] = useState([]);
   const [paymentStatus, setPaymentStatus] = useState(null);
+  const [venue, setVenue] = useState(null);
+  const [events, setEvents] = useState([]);
+  // Evento cuyos asientos se muestran y en el que se compra
+  const [currentEvent, setCurrentEvent] = useState(null);
+  // Lugar en la sala de espera mientras el servidor no admite la sesión
+  const [queue, setQueue] = useState(null);
 
   useEffect(() => {
     WebSocketInstance.connect();
 
     WebSocketInstance.addCallbacks((data) => {
+      let message;
       try {
-        const jsonData = JSON.parse(data);
-        if (Array.isArray(jsonData)) {
-          if (jsonData.length > 0 && jsonData[0].hasOwnProperty('suggestion_number')) {
-            // Es un array de sugerencias con precios
-            setSuggestions(jsonData);
-            setServerMessage('');
-          } else if (jsonData.length > 0 && jsonData[0].hasOwnProperty('section')) {
-            // Es el estado de los asientos
-            setSeatStates(jsonData);
+        message = JSON.parse(data);
+      } catch (e) {
+        console.error('Mensaje inválido del servidor:', data);
+        return;
+      }
+
+      switch (message.type) {
+        case 'queue_position': {
+          // Un token guardado se presenta una sola vez; si es válido se vuelve a guardar
+          const token = sessionStorage.getItem(ADMISSION_TOKEN_KEY);
+          if (token) {
+            sessionStorage.removeItem(ADMISSION_TOKEN_KEY);
+            WebSocketInstance.sendMessage(JSON.stringify({ type: 'present_token', token }));
           }
-        } else if (typeof jsonData === 'object' && jsonData !== null) {
-          // Manejar otros objetos JSON si es necesario
+          setQueue({ position: message.position, etaSecs: message.eta_secs });
+          break;
         }
-      } catch (e) {
-        // Manejo de otros mensajes
-        if (data === 'Sugerencia aceptada' || data === 'Sugerencias rechazadas') {
-          setServerMessage(data);
+        case 'admitted':
+          sessionStorage.setItem(ADMISSION_TOKEN_KEY, message.token);
+          setQueue(null);
+          break;
+        case 'events':
+          setEvents(message.events);
+          break;
+        case 'event_status':
+          // El evento actual abrió, pausó o cerró su venta
+          setEvents((current) =>
+            current.map((event) =>
+              event.id === message.event ? { ...event, status: message.status } : event
+            )
+          );
+          break;
+        case 'venue':
+          setVenue(message);
+          break;
+        case 'seat_states':
+          setCurrentEvent(message.event);
+          setSeatStates(message.seats);
+          break;
+        case 'seat_updates':
+          // Aplicar los cambios publicados por el servidor sobre el estado actual
+          // (la sesión solo recibe los cambios del evento que está mirando)
+          setSeatStates((current) =>
+            current.map((seat) => {
+              const changed = message.seats.find(
+                (update) =>
+                  update.section === seat.section &&
+                  update.row === seat.row &&
+                  update.number === seat.number
+              );
+              return changed || seat;
+            })
+          );
+          break;
+        case 'suggestions':
+          setSuggestions(message.suggestions);
+          setServerMessage(
+            message.suggestions.length === 0 ? 'No se encontraron asientos disponibles' : ''
+          );
+          break;
+        case 'suggestion_accepted':
+          setServerMessage('Sugerencia aceptada');
+          setSuggestions([]);
+          break;
+        case 'suggestions_rejected':
+          setServerMessage('Sugerencias rechazadas');
           setSuggestions([]);
-        } else if (data === 'Pago exitoso') {
-          setServerMessage(data);
+          break;
+        case 'hold_expired':
+          setServerMessage('Su reserva temporal expiró. Solicite los asientos nuevamente.');
+          setSuggestions([]);
+          break;
+        case 'payment_accepted':
+          setServerMessage(
+            `Pago exitoso. Orden ${message.order.id} (clave de acceso ${message.order.access_key})`
+          );
           setPaymentStatus('success');
-        } else if (data === 'Pago fallido. Intente nuevamente.') {
-          setServerMessage(data);
+          break;
+        case 'payment_declined':
+          setServerMessage(`Pago fallido: ${message.reason}. Intente nuevamente.`);
           setPaymentStatus('failure');
-        } else {
-          setServerMessage(data);
-        }
+          break;
+        case 'error':
+          setServerMessage(message.message);
+          break;
+        default:
+          console.warn('Tipo de mensaje desconocido:', message.type);
       }
     });
 
@@ -58,23 +128,47 @@ export const WebSocketProvider = ({ children }) => {
     return () => WebSocketInstance.disconnect();
   }, []);
 
-  const sendSeatRequest = (seatCount, selectedCategory) => {
+  // Cambiar de evento: el servidor responde con el estado de sus asientos
+  const selectEvent = (eventId) => {
+    const event = events.find((e) => e.id === eventId);
+    if (!event) {
+      return;
+    }
+    setVenue(event.venue);
+    setSuggestions([]);
+    WebSocketInstance.sendMessage(JSON.stringify({ type: 'get_state', event: eventId }));
+  };
+
+  const sendSeatRequest = (seatCount, selectedCategory, accessCode) => {
     const seatRequest = {
+      type: 'request_seats',
+      event: currentEvent,
       category: selectedCategory,
       seat_count: parseInt(seatCount),
+      include_upgrades: true,
     };
+    if (accessCode) {
+      seatRequest.access_code = accessCode;
+    }
     WebSocketInstance.sendMessage(JSON.stringify(seatRequest));
   };
 
   const sendChoice = (choice) => {
-    WebSocketInstance.sendMessage(choice.toString());
+    const message = {
+      type: 'choose_suggestion',
+      suggestion_number: choice,
+    };
+    WebSocketInstance.sendMessage(JSON.stringify(message));
+  };
+
+  const sendReject = () => {
+    WebSocketInstance.sendMessage(JSON.stringify({ type: 'reject_suggestions' }));
   };
 
-  const sendPaymentResult = (success, seats) => {
+  const sendPayment = (card) => {
     const message = {
-      type: 'payment_result',
-      success,
-      seats,
+      type: 'pay',
+      card,
     };
     WebSocketInstance.sendMessage(JSON.stringify(message));
   };
@@ -83,14 +177,20 @@ export const WebSocketProvider = ({ children }) => {
     <WebSocketContext.Provider
       value={{
         connected,
+        venue,
+        queue,
+        events,
+        currentEvent,
+        selectEvent,
         suggestions,
         serverMessage,
         seatStates,
         sendSeatRequest,
         sendChoice,
+        sendReject,
         paymentStatus,
         setPaymentStatus,
-        sendPaymentResult,
+        sendPayment,
       }}
     >
       {children}
@@ -31,7 +31,7 @@ Row.propTypes = {
   seats: PropTypes.arrayOf(
     PropTypes.shape({
       number: PropTypes.number.isRequired,
-      booked: PropTypes.string.isRequired, // 'F', 'B', 'R'
+      booked: PropTypes.string.isRequired, // 'F', 'B', 'R', 'X', 'D'
     })
   ).isRequired,
 };
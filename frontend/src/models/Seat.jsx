@@ -28,6 +28,10 @@ class Seat extends React.Component {
       color = 'red'; // Ocupado
     } else if (booked === 'R') {
       color = 'yellow'; // Reservado temporalmente
+    } else if (booked === 'X') {
+      color = 'gray'; // Bloqueado
+    } else if (booked === 'D') {
+      color = 'purple'; // Separación por distanciamiento
     } else if (isSelected) {
       color = 'blue'; // Seleccionado en el front-end
     }
@@ -46,7 +50,7 @@ class Seat extends React.Component {
 
 Seat.propTypes = {
   number: PropTypes.number.isRequired,
-  booked: PropTypes.string.isRequired, // 'F', 'B', 'R'
+  booked: PropTypes.string.isRequired, // 'F', 'B', 'R', 'X', 'D'
   section: PropTypes.string.isRequired,
   row: PropTypes.oneOfType([PropTypes.string, PropTypes.number]).isRequired,
   suggestedSeats: PropTypes.arrayOf(